@@ -2,7 +2,8 @@
 //! Read and write already compressed pixel data blocks.
 //! Does not include the process of compression and decompression.
 
-use crate::meta::attribute::{IntegerBounds};
+use crate::meta::attribute::{IntegerBounds, BlockType, SampleType};
+use half::f16;
 
 /// A generic block of pixel information.
 /// Contains pixel data and an index to the corresponding header.
@@ -196,7 +197,9 @@ impl TileCoordinates {
 
 
 
-use crate::meta::{MetaData, BlockDescription, calculate_block_size};
+use crate::meta::{MetaData, UnknownPart, calculate_block_size};
+use crate::meta::header::Header;
+use crate::compression::Compression;
 
 impl CompressedScanLineBlock {
 
@@ -254,9 +257,9 @@ impl CompressedDeepScanLineBlock {
     /// Read the value without validating.
     pub fn read(read: &mut impl Read, max_block_byte_size: usize) -> Result<Self> {
         let y_coordinate = i32::read(read)?;
-        let compressed_pixel_offset_table_size = u64_to_usize(u64::read(read)?);
-        let compressed_sample_data_size = u64_to_usize(u64::read(read)?);
-        let decompressed_sample_data_size = u64_to_usize(u64::read(read)?);
+        let compressed_pixel_offset_table_size = u64_to_usize_checked(u64::read(read)?, "deep block table size")?;
+        let compressed_sample_data_size = u64_to_usize_checked(u64::read(read)?, "deep block sample data size")?;
+        let decompressed_sample_data_size = u64_to_usize_checked(u64::read(read)?, "deep block sample data size")?;
 
         // doc said i32, try u8
         let compressed_pixel_offset_table = i8::read_vec(
@@ -278,6 +281,73 @@ impl CompressedDeepScanLineBlock {
             compressed_sample_data,
         })
     }
+
+    /// Decode the number of samples in each pixel of this block from the pixel offset table.
+    /// Returns an error if any pixel contains more samples than the header allows,
+    /// or if the total number of samples does not match the size of the sample data.
+    /// Compressed offset tables are not supported yet.
+    pub fn pixel_sample_counts(&self, header: &Header) -> Result<Vec<usize>> {
+        let tile = header.get_scan_line_block_tile_coordinates(self.y_coordinate)?;
+        let pixel_count = header.get_absolute_block_pixel_coordinates(tile)?.size.area();
+        let sample_counts = decode_pixel_sample_counts(header, &self.compressed_pixel_offset_table, pixel_count)?;
+        validate_sample_data_size(header, &sample_counts, self.decompressed_sample_data_size)?;
+        Ok(sample_counts)
+    }
+
+    /// Decode the depth of each sample in the specified pixel of this block, sorted from front to back.
+    /// Each depth is a `(Z, ZBack)` pair. If there is no `ZBack` channel, the `ZBack` value equals `Z`.
+    /// The pixel index counts the pixels of this block row by row, starting at the top left pixel.
+    /// The depth channels may contain `f16` or `f32` samples.
+    /// Compressed sample data is not supported yet.
+    pub fn pixel_depths(&self, header: &Header, pixel_index: usize) -> Result<Vec<(f32, f32)>> {
+        let sample_counts = self.pixel_sample_counts(header)?;
+
+        if self.compressed_sample_data.len() != self.decompressed_sample_data_size {
+            return Err(Error::unsupported("compressed deep sample data"));
+        }
+
+        let sample_count = *sample_counts.get(pixel_index).ok_or(Error::invalid("deep pixel index"))?;
+        let first_sample: usize = sample_counts[.. pixel_index].iter().sum();
+        let total_sample_count: usize = sample_counts.iter().sum();
+
+        // each channel contains the samples of all pixels in this block, one channel after another
+        let read_channel = |name: &[u8]| -> Result<Option<Vec<f32>>> {
+            let channel = header.channels.channels_with_byte_offset()
+                .find(|(_, channel)| channel.name.as_slice() == name);
+
+            let (byte_offset, channel) = match channel {
+                Some(channel) => channel,
+                None => return Ok(None),
+            };
+
+            let sample_size = channel.sample_type.bytes_per_sample();
+            let start = total_sample_count * byte_offset + first_sample * sample_size;
+            let bytes = &mut &self.compressed_sample_data[start .. start + sample_count * sample_size];
+
+            Ok(Some(match channel.sample_type {
+                SampleType::F16 => {
+                    let mut samples = vec![ f16::ZERO; sample_count ];
+                    f16::read_slice(bytes, &mut samples)?;
+                    samples.into_iter().map(f16::to_f32).collect()
+                },
+
+                SampleType::F32 => {
+                    let mut samples = vec![ 0.0; sample_count ];
+                    f32::read_slice(bytes, &mut samples)?;
+                    samples
+                },
+
+                SampleType::U32 => return Err(Error::invalid("depth channel sample type")),
+            }))
+        };
+
+        let depths = read_channel(b"Z")?.ok_or(Error::invalid("deep data without Z channel"))?;
+        let back_depths = read_channel(b"ZBack")?.unwrap_or_else(|| depths.clone());
+
+        let mut depths: Vec<(f32, f32)> = depths.into_iter().zip(back_depths).collect();
+        depths.sort_by(|own, other| own.partial_cmp(other).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(depths)
+    }
 }
 
 
@@ -299,9 +369,9 @@ impl CompressedDeepTileBlock {
     /// Read the value without validating.
     pub fn read(read: &mut impl Read, hard_max_block_byte_size: usize) -> Result<Self> {
         let coordinates = TileCoordinates::read(read)?;
-        let compressed_pixel_offset_table_size = u64_to_usize(u64::read(read)?);
-        let compressed_sample_data_size = u64_to_usize(u64::read(read)?); // TODO u64 just guessed
-        let decompressed_sample_data_size = u64_to_usize(u64::read(read)?);
+        let compressed_pixel_offset_table_size = u64_to_usize_checked(u64::read(read)?, "deep block table size")?;
+        let compressed_sample_data_size = u64_to_usize_checked(u64::read(read)?, "deep block sample data size")?; // TODO u64 just guessed
+        let decompressed_sample_data_size = u64_to_usize_checked(u64::read(read)?, "deep block sample data size")?;
 
         let compressed_pixel_offset_table = i8::read_vec(
             read, compressed_pixel_offset_table_size,
@@ -322,9 +392,63 @@ impl CompressedDeepTileBlock {
             compressed_sample_data,
         })
     }
+
+    /// Decode the number of samples in each pixel of this block from the pixel offset table.
+    /// Returns an error if any pixel contains more samples than the header allows,
+    /// or if the total number of samples does not match the size of the sample data.
+    /// Compressed offset tables are not supported yet.
+    pub fn pixel_sample_counts(&self, header: &Header) -> Result<Vec<usize>> {
+        let pixel_count = header.get_absolute_block_pixel_coordinates(self.coordinates)?.size.area();
+        let sample_counts = decode_pixel_sample_counts(header, &self.compressed_pixel_offset_table, pixel_count)?;
+        validate_sample_data_size(header, &sample_counts, self.decompressed_sample_data_size)?;
+        Ok(sample_counts)
+    }
+}
+
+/// Check that the decompressed sample data contains exactly one sample of each channel per counted sample.
+fn validate_sample_data_size(header: &Header, sample_counts: &[usize], decompressed_sample_data_size: usize) -> UnitResult {
+    let total_sample_count: usize = sample_counts.iter().sum();
+
+    if total_sample_count.checked_mul(header.channels.bytes_per_pixel) != Some(decompressed_sample_data_size) {
+        return Err(Error::invalid("deep sample count not matching sample data size"));
+    }
+
+    Ok(())
+}
+
+/// Convert the cumulative sample counts of a deep pixel offset table to the sample count of each pixel.
+fn decode_pixel_sample_counts(header: &Header, pixel_offset_table: &[i8], pixel_count: usize) -> Result<Vec<usize>> {
+    // like flat blocks, tables that would not get smaller by compression are stored raw
+    if pixel_offset_table.len() != pixel_count * i32::BYTE_SIZE {
+        return Err(
+            if header.compression == Compression::Uncompressed { Error::invalid("deep pixel offset table size") }
+            else { Error::unsupported("compressed deep pixel offset tables") }
+        );
+    }
+
+    let table_bytes: Vec<u8> = pixel_offset_table.iter().map(|&byte| byte as u8).collect();
+    let mut cumulative_counts = vec![0_i32; pixel_count];
+    i32::read_slice(&mut table_bytes.as_slice(), &mut cumulative_counts)?;
+
+    let max_samples = header.max_samples_per_pixel;
+    let mut previous_cumulative_count = 0;
+
+    cumulative_counts.into_iter().map(|cumulative_count| {
+        let sample_count = cumulative_count.checked_sub(previous_cumulative_count)
+            .filter(|&count| count >= 0)
+            .ok_or(Error::invalid("deep pixel offset table"))?;
+
+        let sample_count = i32_to_usize(sample_count, "deep pixel offset table")?;
+        if max_samples.map_or(false, |max| sample_count > max) {
+            return Err(Error::invalid("deep pixel sample count exceeds max samples per pixel"));
+        }
+
+        previous_cumulative_count = cumulative_count;
+        Ok(sample_count)
+    }).collect()
 }
 
-use crate::error::{UnitResult, Result, Error, u64_to_usize, usize_to_i32, i32_to_usize};
+use crate::error::{UnitResult, Result, Error, u64_to_usize_checked, usize_to_i32, i32_to_usize};
 use crate::math::Vec2;
 
 /// Validation of chunks is done while reading and writing the actual data. (For example in exr::full_image)
@@ -353,6 +477,27 @@ impl Chunk {
             "chunk data part number"
         )?;
 
+        Self::read_in_layer(read, meta_data, layer_number)
+    }
+
+    /// Read the value without validating, in a multi-part file that contains parts with an unknown type.
+    /// The part numbers in the file count the unknown parts,
+    /// which are not contained in the meta data, so the part numbers are mapped to the headers.
+    /// Fails for chunks of unknown parts.
+    pub(crate) fn read_of_known_part(read: &mut impl Read, meta_data: &MetaData, unknown_parts: &[UnknownPart]) -> Result<Self> {
+        if unknown_parts.is_empty() { return Self::read(read, meta_data); }
+
+        let part_number = i32_to_usize(i32::read(read)?, "chunk data part number")?;
+        if unknown_parts.iter().any(|part| part.index == part_number) {
+            return Err(Error::invalid("chunk of unknown part"));
+        }
+
+        let skipped_parts = unknown_parts.iter().filter(|part| part.index < part_number).count();
+        Self::read_in_layer(read, meta_data, part_number - skipped_parts)
+    }
+
+    /// Read the value without validating, after the part number has been read.
+    fn read_in_layer(read: &mut impl Read, meta_data: &MetaData, layer_number: usize) -> Result<Self> {
         if layer_number >= meta_data.headers.len() {
             return Err(Error::invalid("chunk data part number"));
         }
@@ -362,18 +507,171 @@ impl Chunk {
 
         let chunk = Chunk {
             layer_index: layer_number,
-            compressed_block: match header.blocks {
+            compressed_block: match header.block_type() {
                 // flat data
-                BlockDescription::ScanLines if !header.deep => CompressedBlock::ScanLine(CompressedScanLineBlock::read(read, max_block_byte_size)?),
-                BlockDescription::Tiles(_) if !header.deep     => CompressedBlock::Tile(CompressedTileBlock::read(read, max_block_byte_size)?),
+                BlockType::ScanLine => CompressedBlock::ScanLine(CompressedScanLineBlock::read(read, max_block_byte_size)?),
+                BlockType::Tile     => CompressedBlock::Tile(CompressedTileBlock::read(read, max_block_byte_size)?),
 
                 // deep data
-                BlockDescription::ScanLines   => CompressedBlock::DeepScanLine(CompressedDeepScanLineBlock::read(read, max_block_byte_size)?),
-                BlockDescription::Tiles(_)    => CompressedBlock::DeepTile(CompressedDeepTileBlock::read(read, max_block_byte_size)?),
+                BlockType::DeepScanLine => CompressedBlock::DeepScanLine(CompressedDeepScanLineBlock::read(read, max_block_byte_size)?),
+                BlockType::DeepTile     => CompressedBlock::DeepTile(CompressedDeepTileBlock::read(read, max_block_byte_size)?),
             },
         };
 
+        // catch corrupt deep data early, where the offset table can be decoded
+        let sample_counts = match chunk.compressed_block {
+            CompressedBlock::DeepScanLine(ref block) => Some(block.pixel_sample_counts(header)),
+            CompressedBlock::DeepTile(ref block) => Some(block.pixel_sample_counts(header)),
+            _ => None,
+        };
+
+        match sample_counts {
+            Some(Err(Error::NotSupported(_))) | Some(Ok(_)) | None => {},
+            Some(Err(error)) => return Err(error),
+        }
+
         Ok(chunk)
     }
 }
 
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::meta::{Requirements, BlockDescription};
+    use crate::meta::attribute::{ChannelDescription, ChannelList, SampleType, Text, LineOrder};
+
+    fn deep_meta_data() -> MetaData {
+        let mut header = Header::new(
+            Text::from("deep"), (3, 2),
+            smallvec::smallvec![ ChannelDescription::named("Z", SampleType::F32) ]
+        ).with_encoding(Compression::Uncompressed, BlockDescription::ScanLines, LineOrder::Increasing);
+
+        header.deep = true;
+        header.deep_data_version = Some(1);
+        header.max_samples_per_pixel = Some(2);
+
+        MetaData {
+            requirements: Requirements {
+                file_format_version: 2, is_single_layer_and_tiled: false,
+                has_long_names: false, has_deep_data: true, has_multiple_layers: false
+            },
+            headers: smallvec::smallvec![ header ],
+        }
+    }
+
+    fn deep_scan_line_chunk(cumulative_sample_counts: &[i32]) -> Vec<u8> {
+        let sample_count = *cumulative_sample_counts.last().unwrap() as usize;
+        deep_scan_line_chunk_with_sample_data(cumulative_sample_counts, sample_count)
+    }
+
+    fn deep_scan_line_chunk_with_sample_data(cumulative_sample_counts: &[i32], sample_count: usize) -> Vec<u8> {
+        let mut table = Vec::new();
+        i32::write_slice(&mut table, cumulative_sample_counts).unwrap();
+
+        let block = CompressedDeepScanLineBlock {
+            y_coordinate: 0,
+            decompressed_sample_data_size: sample_count * f32::BYTE_SIZE,
+            compressed_pixel_offset_table: table.into_iter().map(|byte| byte as i8).collect(),
+            compressed_sample_data: vec![0; sample_count * f32::BYTE_SIZE],
+        };
+
+        let mut bytes = Vec::new();
+        Chunk { layer_index: 0, compressed_block: CompressedBlock::DeepScanLine(block) }
+            .write(&mut bytes, 1).unwrap();
+
+        bytes
+    }
+
+    #[test]
+    fn depths_of_a_pixel_with_two_samples() {
+        let mut meta_data = deep_meta_data();
+        meta_data.headers[0].channels = ChannelList::new(smallvec::smallvec![
+            ChannelDescription::named("Z", SampleType::F32),
+            ChannelDescription::named("ZBack", SampleType::F16),
+        ]);
+
+        // the second pixel contains two samples, the third pixel contains no samples
+        let mut table = Vec::new();
+        i32::write_slice(&mut table, &[1, 3, 3]).unwrap();
+
+        let mut sample_data = Vec::new();
+        f32::write_slice(&mut sample_data, &[5.0, 9.0, 2.0]).unwrap();
+        f16::write_slice(&mut sample_data, &[5.5, 9.5, 2.5].map(f16::from_f32)).unwrap();
+
+        let block = CompressedDeepScanLineBlock {
+            y_coordinate: 0,
+            decompressed_sample_data_size: sample_data.len(),
+            compressed_pixel_offset_table: table.into_iter().map(|byte| byte as i8).collect(),
+            compressed_sample_data: sample_data,
+        };
+
+        let header = &meta_data.headers[0];
+        assert_eq!(block.pixel_depths(header, 0).unwrap(), vec![ (5.0, 5.5) ]);
+        assert_eq!(block.pixel_depths(header, 1).unwrap(), vec![ (2.0, 2.5), (9.0, 9.5) ]);
+        assert_eq!(block.pixel_depths(header, 2).unwrap(), vec![]);
+        assert!(block.pixel_depths(header, 3).is_err());
+    }
+
+    #[test]
+    fn back_depths_equal_front_depths_without_z_back_channel() {
+        let meta_data = deep_meta_data();
+        let bytes = deep_scan_line_chunk(&[0, 2, 2]);
+
+        match Chunk::read(&mut bytes.as_slice(), &meta_data).unwrap().compressed_block {
+            CompressedBlock::DeepScanLine(block) =>
+                assert_eq!(block.pixel_depths(&meta_data.headers[0], 1).unwrap(), vec![ (0.0, 0.0), (0.0, 0.0) ]),
+
+            _ => panic!("expected deep scan line block"),
+        }
+    }
+
+    #[test]
+    fn deep_sample_counts_within_max_samples() {
+        let meta_data = deep_meta_data();
+        assert_eq!(meta_data.headers[0].max_samples_per_pixel, Some(2));
+
+        let bytes = deep_scan_line_chunk(&[1, 3, 5]);
+        let chunk = Chunk::read(&mut bytes.as_slice(), &meta_data).unwrap();
+
+        match chunk.compressed_block {
+            CompressedBlock::DeepScanLine(block) =>
+                assert_eq!(block.pixel_sample_counts(&meta_data.headers[0]).unwrap(), vec![1, 2, 2]),
+
+            _ => panic!("expected deep scan line block"),
+        }
+    }
+
+    #[test]
+    fn reject_deep_sample_count_exceeding_max_samples() {
+        let meta_data = deep_meta_data();
+        let bytes = deep_scan_line_chunk(&[1, 4, 5]);
+
+        match Chunk::read(&mut bytes.as_slice(), &meta_data) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "deep pixel sample count exceeds max samples per pixel"),
+            other => panic!("excessive sample count not rejected: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn reject_deep_sample_counts_not_matching_sample_data() {
+        let meta_data = deep_meta_data();
+        let bytes = deep_scan_line_chunk_with_sample_data(&[1, 2, 3], 5);
+
+        match Chunk::read(&mut bytes.as_slice(), &meta_data) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "deep sample count not matching sample data size"),
+            other => panic!("inconsistent sample data size not rejected: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn reject_deep_offset_table_of_wrong_width() {
+        let meta_data = deep_meta_data();
+        let bytes = deep_scan_line_chunk(&[1, 2]);
+
+        match Chunk::read(&mut bytes.as_slice(), &meta_data) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "deep pixel offset table size"),
+            other => panic!("offset table of wrong width not rejected: {:?}", other.map(|_| ())),
+        }
+    }
+}
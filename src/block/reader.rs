@@ -1,9 +1,11 @@
 //! Composable structures to handle reading an image.
 
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::io::{Read, Seek};
+use std::ops::{ControlFlow, Range};
 use std::sync::mpsc;
 #[cfg(feature = "rayon")]
 use rayon_core::{ThreadPool, ThreadPoolBuildError};
@@ -13,10 +15,12 @@ use smallvec::alloc::sync::Arc;
 use crate::block::{BlockIndex, UncompressedBlock};
 use crate::block::chunk::{Chunk, TileCoordinates};
 use crate::compression::Compression;
-use crate::error::{Error, Result, u64_to_usize, UnitResult};
+use crate::error::{Error, Result, u64_to_usize_checked, usize_to_u64, UnitResult, MISSING_BYTES};
 use crate::io::{PeekRead, Tracking};
-use crate::meta::{MetaData, OffsetTables};
+use crate::meta::{MetaData, OffsetTables, UnknownPart};
+use crate::meta::attribute::AttributeTypeRegistry;
 use crate::meta::header::Header;
+use crate::math::Vec2;
 
 /// Decode the meta data from a byte source, keeping the source ready for further reading.
 /// Continue decoding the remaining bytes by calling `filtered_chunks` or `all_chunks`.
@@ -24,6 +28,8 @@ use crate::meta::header::Header;
 pub struct Reader<R> {
     meta_data: MetaData,
     remaining_reader: PeekRead<Tracking<R>>, // TODO does R need to be Seek or is Tracking enough?
+    reconstruct_offset_tables: bool,
+    unknown_parts: Vec<UnknownPart>,
 }
 
 impl<R: Read + Seek> Reader<R> {
@@ -33,8 +39,35 @@ impl<R: Read + Seek> Reader<R> {
     /// Access it via`meta_data()`.
     pub fn read_from_buffered(read: R, pedantic: bool) -> Result<Self> {
         let mut remaining_reader = PeekRead::new(Tracking::new(read));
-        let meta_data = MetaData::read_validated_from_buffered_peekable(&mut remaining_reader, pedantic)?;
-        Ok(Self { meta_data, remaining_reader })
+        let meta_data = MetaData::read_validated_from_buffered_peekable(&mut remaining_reader, pedantic, None)?;
+        Ok(Self { meta_data, remaining_reader, reconstruct_offset_tables: false, unknown_parts: Vec::new() })
+    }
+
+    /// Start the reading process, parsing all attribute values with the functions of the registry.
+    /// Attributes with types that are not contained in the registry are read as `AttributeValue::Custom`.
+    /// Immediately decodes the meta data into an internal field.
+    pub fn read_from_buffered_with_registry(read: R, pedantic: bool, registry: &AttributeTypeRegistry) -> Result<Self> {
+        let mut remaining_reader = PeekRead::new(Tracking::new(read));
+        let meta_data = MetaData::read_validated_from_buffered_peekable(&mut remaining_reader, pedantic, Some(registry))?;
+        Ok(Self { meta_data, remaining_reader, reconstruct_offset_tables: false, unknown_parts: Vec::new() })
+    }
+
+    /// Start the reading process, skipping all parts of a multi-part file that have an unknown type,
+    /// instead of failing to read the file. Call `unknown_parts()` to find out which parts were skipped.
+    /// The skipped parts are not contained in the meta data, and the chunks of these parts are never read.
+    /// If specified, parses all attribute values with the functions of the registry.
+    /// Immediately decodes the meta data of all other parts into an internal field.
+    pub fn read_from_buffered_skipping_unknown_parts(read: R, pedantic: bool, registry: Option<&AttributeTypeRegistry>) -> Result<Self> {
+        let mut remaining_reader = PeekRead::new(Tracking::new(read));
+        let (meta_data, unknown_parts) = MetaData::read_validated_parts(&mut remaining_reader, pedantic, registry, true)?;
+        Ok(Self { meta_data, remaining_reader, reconstruct_offset_tables: false, unknown_parts })
+    }
+
+    /// When filtering chunks, rebuild the offset tables if they are invalid,
+    /// for example in files that were not written completely.
+    /// The tables are rebuilt by reading all chunks sequentially, as each chunk contains its own size.
+    pub fn reconstruct_offset_tables(self) -> Self {
+        Self { reconstruct_offset_tables: true, ..self }
     }
 
     // must not be mutable, as reading the file later on relies on the meta data
@@ -47,14 +80,23 @@ impl<R: Read + Seek> Reader<R> {
     /// Obtain the meta data ownership.
     pub fn into_meta_data(self) -> MetaData { self.meta_data }
 
+    /// The parts of the file that have been skipped because their type is unknown.
+    /// Always empty, unless the reader was created with `read_from_buffered_skipping_unknown_parts`.
+    pub fn unknown_parts(&self) -> &[UnknownPart] { &self.unknown_parts }
+
     /// Prepare to read all the chunks from the file.
     /// Does not decode the chunks now, but returns a decoder.
     /// Reading all chunks reduces seeking the file, but some chunks might be read without being used.
+    /// Fails if parts with an unknown type have been skipped, as their chunks cannot be read.
     pub fn all_chunks(mut self, pedantic: bool) -> Result<AllChunksReader<R>> {
+        if !self.unknown_parts.is_empty() {
+            return Err(Error::unsupported("reading all chunks of a file with unknown parts"));
+        }
+
         let total_chunk_count = {
             if pedantic {
                 let offset_tables = MetaData::read_offset_tables(&mut self.remaining_reader, &self.meta_data.headers)?;
-                validate_offset_tables(self.meta_data.headers.as_slice(), &offset_tables, self.remaining_reader.byte_position())?;
+                validate_offset_tables(self.meta_data.headers.as_slice(), &offset_tables, self.remaining_reader.byte_position(), false)?;
                 offset_tables.iter().map(|table| table.len()).sum()
             }
             else {
@@ -76,14 +118,24 @@ impl<R: Read + Seek> Reader<R> {
     /// Reading only some chunks may seeking the file, potentially skipping many bytes.
     // TODO tile indices add no new information to block index??
     pub fn filter_chunks(mut self, pedantic: bool, mut filter: impl FnMut(&MetaData, TileCoordinates, BlockIndex) -> bool) -> Result<FilteredChunksReader<R>> {
-        let offset_tables = MetaData::read_offset_tables(&mut self.remaining_reader, &self.meta_data.headers)?;
+        let mut offset_tables = MetaData::read_offset_tables_of_known_parts(
+            &mut self.remaining_reader, &self.meta_data.headers, &self.unknown_parts
+        )?;
+
+        let mut has_missing_chunks = false;
 
         // TODO regardless of pedantic, if invalid, read all chunks instead, and filter after reading each chunk?
-        if pedantic {
-            validate_offset_tables(
+        if pedantic || self.reconstruct_offset_tables {
+            let validation = validate_offset_tables(
                 self.meta_data.headers.as_slice(), &offset_tables,
-                self.remaining_reader.byte_position()
-            )?;
+                self.remaining_reader.byte_position(), !self.unknown_parts.is_empty()
+            );
+
+            if validation.is_err() && self.reconstruct_offset_tables {
+                offset_tables = reconstruct_offset_tables(&mut self.remaining_reader, &self.meta_data, &self.unknown_parts)?;
+                has_missing_chunks = true;
+            }
+            else { validation?; }
         }
 
         let mut filtered_offsets = Vec::with_capacity(
@@ -103,8 +155,13 @@ impl<R: Read + Seek> Reader<R> {
                     pixel_size: data_indices.size,
                 };
 
+                let offset = offset_tables[header_index][block_index]; // safe indexing from `enumerate()`
+
+                // reconstructed tables contain zero for chunks that could not be found, which are skipped
+                if has_missing_chunks && offset == 0 { continue; }
+
                 if filter(&self.meta_data, tile.location, block) {
-                    filtered_offsets.push(offset_tables[header_index][block_index]) // safe indexing from `enumerate()`
+                    filtered_offsets.push(offset)
                 }
             };
         }
@@ -122,21 +179,84 @@ impl<R: Read + Seek> Reader<R> {
             meta_data: self.meta_data,
             expected_filtered_chunk_count: filtered_offsets.len(),
             remaining_filtered_chunk_indices: filtered_offsets.into_iter(),
-            remaining_bytes: self.remaining_reader
+            remaining_bytes: self.remaining_reader,
+            unknown_parts: self.unknown_parts,
+        })
+    }
+
+    /// Decompress only the scan line blocks of a layer that cover the specified rows,
+    /// using the offset table to skip all other blocks in the file.
+    /// The rows are specified relative to the data window, starting at zero.
+    ///
+    /// Returns a single block that contains all decompressed lines.
+    /// As blocks contain multiple scan lines, the returned block may cover more rows than requested.
+    /// The actually covered rows can be found in the returned block index.
+    pub fn read_scan_line_region(self, layer_index: usize, rows: Range<usize>, pedantic: bool) -> Result<UncompressedBlock> {
+        let header = self.meta_data.headers.get(layer_index)
+            .ok_or(Error::invalid("layer index"))?;
+
+        if header.deep { return Err(Error::unsupported("deep data not supported yet")); }
+        if header.blocks.has_tiles() { return Err(Error::invalid("scan line region of a tiled image")); }
+
+        if rows.start >= rows.end || rows.end > header.layer_size.height() {
+            return Err(Error::invalid("scan line region"));
+        }
+
+        let width = header.layer_size.width();
+
+        let chunks = self.filter_chunks(pedantic, |_, _, block| {
+            block.layer == layer_index
+                && block.pixel_position.y() < rows.end
+                && block.pixel_position.y() + block.pixel_size.height() > rows.start
+        })?;
+
+        let mut blocks = Vec::with_capacity(chunks.expected_chunk_count());
+        chunks.decompress_sequential(pedantic, |_, block| { blocks.push(block); Ok(()) })?;
+
+        // the lines of consecutive scan line blocks can simply be concatenated
+        blocks.sort_unstable_by_key(|block| block.index.pixel_position.y());
+
+        let first_row = blocks.first().map(|block| block.index.pixel_position.y())
+            .ok_or(Error::invalid("missing scan line blocks"))?;
+
+        let mut data = Vec::with_capacity(blocks.iter().map(|block| block.data.len()).sum());
+        let mut next_row = first_row;
+
+        for block in blocks {
+            if block.index.pixel_position.y() != next_row {
+                return Err(Error::invalid("missing scan line blocks"));
+            }
+
+            next_row += block.index.pixel_size.height();
+            data.extend_from_slice(&block.data);
+        }
+
+        Ok(UncompressedBlock {
+            index: BlockIndex {
+                layer: layer_index,
+                pixel_position: Vec2(0, first_row),
+                pixel_size: Vec2(width, next_row - first_row),
+                level: Vec2(0, 0),
+            },
+            data,
         })
     }
 }
 
 
-fn validate_offset_tables(headers: &[Header], offset_tables: &OffsetTables, chunks_start_byte: usize) -> UnitResult {
+fn validate_offset_tables(
+    headers: &[Header], offset_tables: &OffsetTables, chunks_start_byte: usize, has_unknown_parts: bool
+) -> UnitResult {
     let max_pixel_bytes: usize = headers.iter() // when compressed, chunks are smaller, but never larger than max
         .map(|header| header.max_pixel_file_bytes())
         .sum();
 
-    // check that each offset is within the bounds
-    let end_byte = chunks_start_byte + max_pixel_bytes;
-    let is_invalid = offset_tables.iter().flatten().map(|&u64| u64_to_usize(u64))
-        .any(|chunk_start| chunk_start < chunks_start_byte || chunk_start > end_byte);
+    // check that each offset is within the bounds.
+    // the size of the chunks of unknown parts is not known, so there is no upper bound in that case
+    let end_byte = if has_unknown_parts { usize::MAX } else { chunks_start_byte + max_pixel_bytes };
+    let is_invalid = offset_tables.iter().flatten()
+        .map(|&offset| u64_to_usize_checked(offset, "offset table"))
+        .any(|chunk_start| chunk_start.map_or(true, |chunk_start| chunk_start < chunks_start_byte || chunk_start > end_byte));
 
     if is_invalid { Err(Error::invalid("offset table")) }
     else { Ok(()) }
@@ -145,6 +265,50 @@ fn validate_offset_tables(headers: &[Header], offset_tables: &OffsetTables, chun
 
 
 
+/// Find the start of each chunk by reading all chunks sequentially, starting at the current position.
+/// Stops at the first chunk that cannot be read, for example in a truncated file.
+/// The offsets of all chunks that were not found remain zero.
+/// Afterwards, seeks back to the first chunk.
+/// Chunks of unknown parts cannot be read, so the search also stops at the first chunk of an unknown part.
+fn reconstruct_offset_tables<R: Read + Seek>(
+    read: &mut PeekRead<Tracking<R>>, meta_data: &MetaData, unknown_parts: &[UnknownPart]
+) -> Result<OffsetTables> {
+    let chunks_start_byte = read.byte_position();
+
+    // the offset tables are sorted by the index of the block in increasing y order
+    let block_indices: Vec<HashMap<TileCoordinates, usize>> = meta_data.headers.iter()
+        .map(|header| header.blocks_increasing_y_order().enumerate()
+            .map(|(index, tile)| (tile.location, index)).collect())
+        .collect();
+
+    let mut offset_tables: OffsetTables = meta_data.headers.iter()
+        .map(|header| vec![0; header.chunk_count]).collect();
+
+    for _ in 0 .. offset_tables.iter().map(|table| table.len()).sum::<usize>() {
+        let chunk_start_byte = read.byte_position();
+
+        let block_index = read_complete_chunk(read, meta_data, unknown_parts).and_then(|chunk| {
+            let header = &meta_data.headers[chunk.layer_index];
+            let tile = header.get_block_data_indices(&chunk.compressed_block)?;
+            let block_index = *block_indices[chunk.layer_index].get(&tile)
+                .ok_or(Error::invalid("chunk tile coordinates"))?;
+
+            Ok((chunk.layer_index, block_index))
+        });
+
+        // the following bytes cannot be trusted, so the remaining chunks cannot be found
+        let (layer_index, block_index) = match block_index {
+            Ok(index) => index,
+            Err(_) => break,
+        };
+
+        offset_tables[layer_index][block_index] = usize_to_u64(chunk_start_byte);
+    }
+
+    read.skip_to(chunks_start_byte)?;
+    Ok(offset_tables)
+}
+
 /// Decode the desired chunks and skip the unimportant chunks in the file.
 /// The decoded chunks can be decompressed by calling
 /// `decompress_parallel`, `decompress_sequential`, or `sequential_decompressor` or `parallel_decompressor`.
@@ -156,6 +320,16 @@ pub struct FilteredChunksReader<R> {
     expected_filtered_chunk_count: usize,
     remaining_filtered_chunk_indices: std::vec::IntoIter<u64>,
     remaining_bytes: PeekRead<Tracking<R>>,
+    unknown_parts: Vec<UnknownPart>,
+}
+
+#[cfg(feature = "async")]
+impl<R> FilteredChunksReader<R> {
+
+    /// The byte positions of the chunks that have not been read yet, in increasing order.
+    pub(crate) fn remaining_chunk_offsets(&self) -> &[u64] {
+        self.remaining_filtered_chunk_indices.as_slice()
+    }
 }
 
 /// Decode all chunks in the file without seeking.
@@ -183,6 +357,34 @@ pub struct OnProgressChunksReader<R, F> {
     callback: F,
 }
 
+/// Receives the reading progress and decides whether reading should continue.
+/// Implemented for all `FnMut(f64)` closures, which never cancel reading.
+/// Wrap a closure in `Cancellable` to be able to cancel reading.
+pub trait ProgressCallback {
+
+    /// Called with the fraction of chunks that have been read, from `0.0` to `1.0`.
+    /// Return `ControlFlow::Break` to abort reading, which results in `Error::Cancelled`.
+    fn report_progress(&mut self, progress: f64) -> ControlFlow<()>;
+}
+
+impl<F> ProgressCallback for F where F: FnMut(f64) {
+    fn report_progress(&mut self, progress: f64) -> ControlFlow<()> {
+        self(progress);
+        ControlFlow::Continue(())
+    }
+}
+
+/// A progress callback that can cancel reading,
+/// by returning `ControlFlow::Break` from the wrapped closure.
+#[derive(Debug, Clone, Copy)]
+pub struct Cancellable<F>(pub F);
+
+impl<F> ProgressCallback for Cancellable<F> where F: FnMut(f64) -> ControlFlow<()> {
+    fn report_progress(&mut self, progress: f64) -> ControlFlow<()> {
+        (self.0)(progress)
+    }
+}
+
 /// Decode chunks in the file.
 /// The decoded chunks can be decompressed by calling
 /// `decompress_parallel`, `decompress_sequential`, or `sequential_decompressor`.
@@ -209,7 +411,8 @@ pub trait ChunksReader: Sized + Iterator<Item=Result<Chunk>> + ExactSizeIterator
     /// callback for each chunk that is read from the file.
     /// If the file can be successfully decoded,
     /// the progress will always at least once include 0.0 at the start and 1.0 at the end.
-    fn on_progress<F>(self, on_progress: F) -> OnProgressChunksReader<Self, F> where F: FnMut(f64) {
+    /// Use a `Cancellable` callback to be able to abort reading.
+    fn on_progress<F>(self, on_progress: F) -> OnProgressChunksReader<Self, F> where F: ProgressCallback {
         OnProgressChunksReader { chunks_reader: self, callback: on_progress, decoded_chunks: 0 }
     }
 
@@ -269,25 +472,24 @@ pub trait ChunksReader: Sized + Iterator<Item=Result<Chunk>> + ExactSizeIterator
     }
 }
 
-impl<R, F> ChunksReader for OnProgressChunksReader<R, F> where R: ChunksReader, F: FnMut(f64) {
+impl<R, F> ChunksReader for OnProgressChunksReader<R, F> where R: ChunksReader, F: ProgressCallback {
     fn meta_data(&self) -> &MetaData { self.chunks_reader.meta_data() }
     fn expected_chunk_count(&self) -> usize { self.chunks_reader.expected_chunk_count() }
 }
 
-impl<R, F> ExactSizeIterator for OnProgressChunksReader<R, F> where R: ChunksReader, F: FnMut(f64) {}
-impl<R, F> Iterator for OnProgressChunksReader<R, F> where R: ChunksReader, F: FnMut(f64) {
+impl<R, F> ExactSizeIterator for OnProgressChunksReader<R, F> where R: ChunksReader, F: ProgressCallback {}
+impl<R, F> Iterator for OnProgressChunksReader<R, F> where R: ChunksReader, F: ProgressCallback {
     type Item = Result<Chunk>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.chunks_reader.next().map(|item|{
-            {
-                let total_chunks = self.expected_chunk_count() as f64;
-                let callback = &mut self.callback;
-                callback(self.decoded_chunks as f64 / total_chunks);
-            }
+            let total_chunks = self.expected_chunk_count() as f64;
+            let flow = self.callback.report_progress(self.decoded_chunks as f64 / total_chunks);
 
             self.decoded_chunks += 1;
-            item
+
+            if flow.is_break() { Err(Error::Cancelled) }
+            else { item }
         })
             .or_else(||{
                 debug_assert_eq!(
@@ -295,8 +497,8 @@ impl<R, F> Iterator for OnProgressChunksReader<R, F> where R: ChunksReader, F: F
                     "chunks reader finished but not all chunks are decompressed"
                 );
 
-                let callback = &mut self.callback;
-                callback(1.0);
+                // reading is complete, so there is nothing left to cancel
+                let _ = self.callback.report_progress(1.0);
                 None
             })
     }
@@ -318,7 +520,7 @@ impl<R: Read + Seek> Iterator for AllChunksReader<R> {
     fn next(&mut self) -> Option<Self::Item> {
         // read as many chunks as the file should contain (inferred from meta data)
         let next_chunk = self.remaining_chunks.next()
-            .map(|_| Chunk::read(&mut self.remaining_bytes, &self.meta_data));
+            .map(|_| read_complete_chunk(&mut self.remaining_bytes, &self.meta_data, &[]));
 
         // if no chunks are left, but some bytes remain, return error
         if self.pedantic && next_chunk.is_none() && self.remaining_bytes.peek_u8().is_ok() {
@@ -351,7 +553,7 @@ impl<R: Read + Seek> Iterator for FilteredChunksReader<R> {
             )?;
 
             let meta_data = &self.meta_data;
-            Chunk::read(&mut self.remaining_bytes, meta_data)
+            read_complete_chunk(&mut self.remaining_bytes, meta_data, &self.unknown_parts)
         })
 
         // TODO remember last chunk index and then seek to index+size and check whether bytes are left?
@@ -362,6 +564,15 @@ impl<R: Read + Seek> Iterator for FilteredChunksReader<R> {
     }
 }
 
+/// Read a chunk, reporting a file that ends within the chunk as truncated.
+/// Any bytes after the last chunk are never read, so trailing padding is ignored.
+fn read_complete_chunk(read: &mut impl Read, meta_data: &MetaData, unknown_parts: &[UnknownPart]) -> Result<Chunk> {
+    Chunk::read_of_known_part(read, meta_data, unknown_parts).map_err(|error| match error {
+        Error::Invalid(message) if message == MISSING_BYTES => Error::invalid("truncated chunk"),
+        error => error,
+    })
+}
+
 /// Read all chunks from the file, decompressing each chunk immediately.
 /// Implements iterator.
 #[derive(Debug)]
@@ -530,6 +741,47 @@ impl<R: ChunksReader> Iterator for ParallelBlockDecompressor<R> {
 }
 
 
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use crate::block::lines::LineRef;
+    use std::io::Cursor;
+
+    #[test]
+    fn read_scan_line_region_matches_full_image() {
+        let size = Vec2(7, 300);
+        let image = Image::from_encoded_channels(
+            size, Encoding { compression: Compression::ZIP16, .. Encoding::UNCOMPRESSED },
+            SpecificChannels::rgb(|Vec2(x, y)| (x as f32, y as f32, (x * y) as f32))
+        );
+
+        let mut bytes = Vec::new();
+        image.write().to_buffered(Cursor::new(&mut bytes)).unwrap();
+
+        let read_region = |rows| crate::block::read(Cursor::new(&bytes), true).unwrap()
+            .read_scan_line_region(0, rows, true).unwrap();
+
+        let full = read_region(0 .. size.height());
+        assert_eq!(full.index.pixel_size, size);
+
+        let region = read_region(100 .. 132);
+        let covered_rows = region.index.pixel_position.y() .. region.index.pixel_position.y() + region.index.pixel_size.height();
+        assert_eq!(covered_rows, 96 .. 144, "zip16 blocks contain 16 rows");
 
+        let meta = MetaData::read_from_buffered(bytes.as_slice(), true).unwrap();
+        let channels = &meta.headers[0].channels;
 
+        let full_lines: Vec<LineRef<'_>> = full.lines(channels)
+            .filter(|line| covered_rows.contains(&line.location.position.y()))
+            .collect();
 
+        let region_lines: Vec<LineRef<'_>> = region.lines(channels).collect();
+        assert_eq!(region_lines, full_lines);
+
+        let blue_of_row_120 = region_lines.iter()
+            .find(|line| line.location.position.y() == 120 && line.location.channel == 0)
+            .unwrap().read_samples::<f32>().collect::<Result<Vec<f32>>>().unwrap();
+
+        assert_eq!(blue_of_row_120, (0..7).map(|x| (x * 120) as f32).collect::<Vec<f32>>());
+    }
+}
@@ -0,0 +1,311 @@
+//! Stitch the decompressed blocks of a single layer back together,
+//! regardless of the order in which they appear in the file.
+//! This is useful for files with `LineOrder::Unspecified`,
+//! where tiles may be stored in any order.
+
+use smallvec::SmallVec;
+use crate::block::UncompressedBlock;
+use crate::compression::ByteVec;
+use crate::error::{Error, Result};
+use crate::math::Vec2;
+use crate::meta::{BlockDescription, mip_map_levels, rip_map_levels};
+use crate::meta::attribute::LevelMode;
+use crate::meta::header::Header;
+
+
+/// The assembled pixel bytes of all resolution levels of a single layer.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TiledImage {
+
+    /// Index of the layer that the blocks were assembled from.
+    pub layer_index: usize,
+
+    /// The position of the top left pixel of the largest level in the data window.
+    pub layer_position: Vec2<i32>,
+
+    /// The resolution levels of the layer, largest level first.
+    /// Contains exactly one level if the layer is not mip or rip mapped.
+    pub levels: Vec<TiledLevel>,
+
+    /// The sampling rate of each channel, in the order of the channel list.
+    pub channel_sampling: SmallVec<[Vec2<usize>; 4]>,
+}
+
+/// Scan line layers are assembled the same way as tiled layers, but contain only a single level.
+pub type ScanLineImage = TiledImage;
+
+/// The bytes of a single row of a level, one slice per channel, in the order of the channel list.
+/// The slice of a subsampled channel is empty in rows that contain no samples of that channel.
+pub type RowView<'s> = SmallVec<[&'s [u8]; 4]>;
+
+/// The assembled pixel bytes of a single resolution level.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TiledLevel {
+
+    /// Index of this mip or rip level.
+    pub level_index: Vec2<usize>,
+
+    /// Number of pixels in this level.
+    pub resolution: Vec2<usize>,
+
+    /// One byte buffer per channel, in the order of the channel list.
+    /// Each buffer contains the samples of the channel row by row, without padding,
+    /// encoded in the native byte layout of the uncompressed block (f16, f32, or u32).
+    /// Subsampled channels contain only the rows and columns that have samples.
+    pub channels: SmallVec<[ByteVec; 4]>,
+}
+
+
+impl TiledImage {
+
+    /// Place each decompressed block at its level and pixel position.
+    /// The blocks may be supplied in any order.
+    /// Pixels that are not covered by any block remain zero.
+    /// Returns an error if a block does not belong to this layer or does not fit into its level.
+    pub fn assemble(header: &Header, layer_index: usize, blocks: impl IntoIterator<Item=UncompressedBlock>) -> Result<Self> {
+        if header.deep {
+            return Err(Error::unsupported("assembling deep data blocks"));
+        }
+
+        // the lines of a block do not account for subsampling yet
+        if header.channels.list.iter().any(|channel| channel.sampling != Vec2(1, 1)) {
+            return Err(Error::unsupported("assembling subsampled channels"));
+        }
+
+        let mut levels: Vec<TiledLevel> = level_resolutions(header)
+            .map(|(level_index, resolution)| TiledLevel {
+                level_index, resolution,
+                channels: header.channels.list.iter()
+                    .map(|channel| vec![0_u8; channel.subsampled_resolution(resolution).area() * channel.sample_type.bytes_per_sample()])
+                    .collect()
+            })
+            .collect();
+
+        for block in blocks {
+            if block.index.layer != layer_index {
+                return Err(Error::invalid("block layer index"));
+            }
+
+            let level = levels.iter_mut()
+                .find(|level| level.level_index == block.index.level)
+                .ok_or(Error::invalid("block level index"))?;
+
+            let block_end = block.index.pixel_position + block.index.pixel_size;
+            if block_end.x() > level.resolution.x() || block_end.y() > level.resolution.y() {
+                return Err(Error::invalid("block pixel position"));
+            }
+
+            if block.data.len() != block.index.pixel_size.area() * header.channels.bytes_per_pixel {
+                return Err(Error::invalid("block byte size"));
+            }
+
+            for line in block.lines(&header.channels) {
+                let sample_size = header.channels.list[line.location.channel].sample_type.bytes_per_sample();
+                let start = (line.location.position.y() * level.resolution.width() + line.location.position.x()) * sample_size;
+
+                level.channels[line.location.channel][start .. start + line.value.len()]
+                    .copy_from_slice(line.value);
+            }
+        }
+
+        let channel_sampling = header.channels.list.iter().map(|channel| channel.sampling).collect();
+        Ok(TiledImage { layer_index, layer_position: header.own_attributes.layer_position, levels, channel_sampling })
+    }
+
+    /// Find the assembled level with the specified mip or rip level index.
+    pub fn level(&self, level_index: Vec2<usize>) -> Option<&TiledLevel> {
+        self.levels.iter().find(|level| level.level_index == level_index)
+    }
+
+    /// Iterate the rows of the largest level from top to bottom,
+    /// regardless of the line order of the blocks in the file.
+    /// Yields the y coordinate of each row in the data window, along with the bytes of each channel.
+    pub fn rows(&self) -> impl '_ + Iterator<Item=(i32, RowView<'_>)> {
+        let level = &self.levels[0];
+
+        (0 .. level.resolution.height()).map(move |y| {
+            let row = level.channels.iter().zip(&self.channel_sampling)
+                .map(|(channel, sampling)| {
+                    if y % sampling.y() != 0 { return &channel[.. 0]; }
+
+                    let sampled_rows = level.resolution.height() / sampling.y();
+                    let row_byte_count = channel.len() / sampled_rows;
+                    let sampled_y = y / sampling.y();
+                    &channel[sampled_y * row_byte_count .. (sampled_y + 1) * row_byte_count]
+                })
+                .collect();
+
+            (self.layer_position.y() + y as i32, row)
+        })
+    }
+}
+
+/// The index and resolution of all levels in the layer, largest level first.
+fn level_resolutions(header: &Header) -> Box<dyn Iterator<Item=(Vec2<usize>, Vec2<usize>)>> {
+    match header.blocks {
+        BlockDescription::Tiles(tiles) => match tiles.level_mode {
+            LevelMode::Singular => Box::new(std::iter::once((Vec2(0, 0), header.layer_size))),
+
+            LevelMode::MipMap => Box::new(
+                mip_map_levels(tiles.rounding_mode, header.layer_size)
+                    .map(|(index, resolution)| (Vec2(index, index), resolution))
+            ),
+
+            LevelMode::RipMap => Box::new(rip_map_levels(tiles.rounding_mode, header.layer_size)),
+        },
+
+        BlockDescription::ScanLines => Box::new(std::iter::once((Vec2(0, 0), header.layer_size))),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::seq::SliceRandom;
+    use crate::meta::attribute::{ChannelDescription, SampleType, TileDescription, LineOrder};
+    use crate::math::RoundingMode;
+    use crate::compression::Compression;
+    use crate::block::enumerate_ordered_header_block_indices;
+
+    fn sample_value(level: Vec2<usize>, channel: usize, position: Vec2<usize>) -> f32 {
+        (level.x() * 10_000 + level.y() * 1_000 + channel * 100 + position.y() * 10 + position.x()) as f32
+    }
+
+    #[test]
+    fn assemble_shuffled_rip_map_tiles() {
+        let header = Header::new(
+            "layer".into(), (13, 7),
+            smallvec::smallvec![
+                ChannelDescription::named("A", SampleType::F32),
+                ChannelDescription::named("B", SampleType::F32),
+            ]
+        ).with_encoding(
+            Compression::Uncompressed,
+            BlockDescription::Tiles(TileDescription {
+                tile_size: Vec2(4, 3),
+                level_mode: LevelMode::RipMap,
+                rounding_mode: RoundingMode::Up,
+            }),
+            LineOrder::Unspecified
+        );
+
+        let headers = [header];
+        let mut blocks: Vec<UncompressedBlock> = enumerate_ordered_header_block_indices(&headers)
+            .map(|(_, index)| UncompressedBlock::from_lines(&headers[0].channels, index, |line| {
+                let location = line.location;
+                line.write_samples(|x| sample_value(location.level, location.channel, location.position + Vec2(x, 0)))
+                    .expect("write to in-memory line failed");
+            }))
+            .collect();
+
+        assert!(blocks.len() > 1);
+        blocks.shuffle(&mut rand::rngs::StdRng::seed_from_u64(7));
+
+        let image = TiledImage::assemble(&headers[0], 0, blocks).unwrap();
+        assert_eq!(image.levels.len(), 5 * 4);
+
+        for level in &image.levels {
+            for (channel_index, channel) in level.channels.iter().enumerate() {
+                let mut samples = vec![0.0_f32; level.resolution.area()];
+                <f32 as crate::io::Data>::read_slice(&mut channel.as_slice(), &mut samples).unwrap();
+
+                for (index, &sample) in samples.iter().enumerate() {
+                    let position = Vec2(index % level.resolution.width(), index / level.resolution.width());
+                    assert_eq!(sample, sample_value(level.level_index, channel_index, position));
+                }
+            }
+        }
+
+        assert_eq!(image.level(Vec2(1, 2)).unwrap().resolution, Vec2(7, 2));
+    }
+
+    #[test]
+    fn rows_from_top_to_bottom_for_any_line_order() {
+        for &line_order in &[LineOrder::Increasing, LineOrder::Decreasing] {
+            let header = Header::new(
+                "layer".into(), (5, 6),
+                smallvec::smallvec![ ChannelDescription::named("A", SampleType::F32) ]
+            )
+                .with_encoding(Compression::Uncompressed, BlockDescription::ScanLines, line_order)
+                .with_position(Vec2(3, -4));
+
+            let headers = [header];
+            let mut blocks: Vec<UncompressedBlock> = enumerate_ordered_header_block_indices(&headers)
+                .map(|(_, index)| UncompressedBlock::from_lines(&headers[0].channels, index, |line| {
+                    let y = line.location.position.y();
+                    line.write_samples(|_| y as f32).expect("write to in-memory line failed");
+                }))
+                .collect();
+
+            // the blocks appear in the file in the order specified by the line order
+            if line_order == LineOrder::Decreasing { blocks.reverse(); }
+
+            let image = ScanLineImage::assemble(&headers[0], 0, blocks).unwrap();
+            let rows: Vec<(i32, RowView<'_>)> = image.rows().collect();
+
+            assert_eq!(rows.len(), 6);
+            assert_eq!(rows[0].0, -4, "first row must be the top of the data window");
+
+            for (index, (y, row)) in rows.iter().enumerate() {
+                assert_eq!(*y, -4 + index as i32);
+
+                let mut samples = [0.0_f32; 5];
+                <f32 as crate::io::Data>::read_slice(&mut &row[0][..], &mut samples).unwrap();
+                assert_eq!(samples, [index as f32; 5]);
+            }
+        }
+    }
+
+    #[test]
+    fn rows_of_subsampled_channels() {
+        let resolution = Vec2(4, 4);
+
+        // the second channel only has samples in every second row and column
+        let image = ScanLineImage {
+            layer_index: 0,
+            layer_position: Vec2(0, 0),
+            channel_sampling: smallvec::smallvec![ Vec2(1, 1), Vec2(2, 2) ],
+            levels: vec![ TiledLevel {
+                level_index: Vec2(0, 0), resolution,
+                channels: smallvec::smallvec![ (0 .. 16).collect(), (0 .. 4).collect() ],
+            } ],
+        };
+
+        let rows: Vec<(i32, RowView<'_>)> = image.rows().collect();
+        assert_eq!(rows.len(), 4);
+
+        for (y, row) in &rows {
+            let y = *y as u8;
+            assert_eq!(row[0], &[ y * 4, y * 4 + 1, y * 4 + 2, y * 4 + 3 ][..]);
+
+            if y % 2 == 0 { assert_eq!(row[1], &[ y, y + 1 ][..]); }
+            else { assert!(row[1].is_empty()); }
+        }
+
+        let mut header = Header::new(
+            "layer".into(), resolution,
+            smallvec::smallvec![ ChannelDescription::named("A", SampleType::F16) ]
+        );
+
+        header.channels.list[0].sampling = Vec2(2, 2);
+        assert!(ScanLineImage::assemble(&header, 0, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn reject_block_of_other_layer() {
+        let header = Header::new(
+            "layer".into(), (4, 4),
+            smallvec::smallvec![ ChannelDescription::named("A", SampleType::F16) ]
+        );
+
+        let headers = [header];
+        let mut blocks: Vec<UncompressedBlock> = enumerate_ordered_header_block_indices(&headers)
+            .map(|(_, index)| UncompressedBlock::from_lines(&headers[0].channels, index, |_| {}))
+            .collect();
+
+        blocks[0].index.layer = 1;
+        assert!(TiledImage::assemble(&headers[0], 0, blocks).is_err());
+    }
+}
@@ -202,7 +202,11 @@ impl<W> ChunkWriter<W> where W: Write + Seek {
         }
 
         // write all offset tables
-        debug_assert_ne!(self.byte_writer.byte_position(), self.chunk_indices_byte_location.end, "offset table has already been updated");
+        // (an empty image has no chunks, so the position does not change in that case)
+        debug_assert!(
+            self.chunk_count == 0 || self.byte_writer.byte_position() != self.chunk_indices_byte_location.end,
+            "offset table has already been updated"
+        );
         self.byte_writer.seek_write_to(self.chunk_indices_byte_location.start)?;
 
         for table in self.chunk_indices_increasing_y {
@@ -473,3 +477,60 @@ impl<'w, W> ParallelBlocksCompressor<'w, W> where W: 'w + ChunksWriter {
 
 
 
+
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+    use crate::io::PeekRead;
+    use crate::block::chunk::Chunk;
+    use std::convert::TryFrom;
+    use std::io::Cursor;
+
+    #[test]
+    fn offset_tables_point_to_chunks() {
+        let scan_lines = Layer::new(
+            (17, 123), LayerAttributes::named("scan lines"),
+            Encoding { compression: Compression::ZIP16, .. Encoding::UNCOMPRESSED },
+            SpecificChannels::rgb(|Vec2(x, y)| (x as f32, y as f32, 0.5_f32))
+        );
+
+        let tiles = Layer::new(
+            (17, 123), LayerAttributes::named("tiles"),
+            Encoding::FAST_LOSSLESS,
+            SpecificChannels::rgba(|Vec2(x, y)| (x as f32, y as f32, 0.5_f32, 1.0_f32))
+        );
+
+        let image = Image::empty(ImageAttributes::new(IntegerBounds::from_dimensions((17, 123))))
+            .with_layer(scan_lines).with_layer(tiles);
+
+        let mut bytes = Vec::new();
+        image.write().to_buffered(Cursor::new(&mut bytes)).unwrap();
+
+        let mut read = PeekRead::new(bytes.as_slice());
+        let meta = MetaData::read_validated_from_buffered_peekable(&mut read, true, None).unwrap();
+        let offset_tables = MetaData::read_offset_tables(&mut read, &meta.headers).unwrap();
+
+        let mut chunk_ranges = Vec::new();
+
+        for (layer_index, (header, offsets)) in meta.headers.iter().zip(&offset_tables).enumerate() {
+            assert_eq!(offsets.len(), header.chunk_count);
+
+            for (tile, &offset) in header.blocks_increasing_y_order().zip(offsets) {
+                let start = usize::try_from(offset).unwrap();
+                let mut chunk_bytes = &bytes[start ..];
+
+                let chunk = Chunk::read(&mut chunk_bytes, &meta).unwrap();
+                assert_eq!(chunk.layer_index, layer_index);
+                assert_eq!(header.get_block_data_indices(&chunk.compressed_block).unwrap(), tile.location);
+
+                chunk_ranges.push(start .. bytes.len() - chunk_bytes.len());
+            }
+        }
+
+        // chunks follow each other without gaps, until the end of the file
+        chunk_ranges.sort_by_key(|range| range.start);
+        for pair in chunk_ranges.windows(2) { assert_eq!(pair[0].end, pair[1].start); }
+        assert_eq!(chunk_ranges.last().unwrap().end, bytes.len());
+    }
+}
@@ -15,7 +15,7 @@ use std::convert::TryInto;
 use std::mem::size_of;
 use half::f16;
 use crate::meta::attribute::{IntegerBounds, SampleType, ChannelList};
-use crate::error::{Result, Error, usize_to_i32};
+use crate::error::{Result, Error, UnitResult, usize_to_i32};
 use crate::meta::header::Header;
 
 
@@ -207,7 +207,7 @@ impl Compression {
 
         let expected_byte_size = pixel_section.size.area() * header.channels.bytes_per_pixel; // FIXME this needs to account for subsampling anywhere
 
-        // note: always true where self == Uncompressed
+        // note: always true where self == Uncompressed, unless the chunk is corrupt
         if compressed.len() == expected_byte_size {
             // the compressed data was larger than the raw data, so the small raw data has been written
             Ok(convert_little_endian_to_current(compressed, &header.channels, pixel_section))
@@ -215,7 +215,7 @@ impl Compression {
         else {
             use self::Compression::*;
             let bytes = match self {
-                Uncompressed => Ok(convert_little_endian_to_current(compressed, &header.channels, pixel_section)),
+                Uncompressed => return Err(Error::invalid("chunk size mismatch")),
                 ZIP16 => zip::decompress_bytes(&header.channels, compressed, pixel_section, expected_byte_size, pedantic),
                 ZIP1 => zip::decompress_bytes(&header.channels, compressed, pixel_section, expected_byte_size, pedantic),
                 RLE => rle::decompress_bytes(&header.channels, compressed, pixel_section, expected_byte_size, pedantic),
@@ -238,7 +238,7 @@ impl Compression {
                 })?;
 
             if bytes.len() != expected_byte_size {
-                Err(Error::invalid("decompressed data"))
+                Err(Error::invalid("chunk size mismatch"))
             }
 
             else { Ok(bytes) }
@@ -289,6 +289,44 @@ impl Compression {
         }
     }
 
+    /// Whether this compression method is lossy for at least some sample types.
+    /// The same as `may_loose_data`.
+    pub fn is_lossy(self) -> bool {
+        self.may_loose_data()
+    }
+
+    /// The compression level of `DWAA` and `DWAB` if the file does not specify one.
+    pub const DWA_DEFAULT_COMPRESSION_LEVEL: f32 = 45.0;
+
+    /// The canonical short name of this compression method, as used by the OpenEXR command line tools.
+    /// For example, `ZIP1` is named `zips` and `ZIP16` is named `zip`.
+    pub fn name(self) -> &'static str {
+        use self::Compression::*;
+        match self {
+            Uncompressed => "none",
+            RLE => "rle",
+            ZIP1 => "zips",
+            ZIP16 => "zip",
+            PIZ => "piz",
+            PXR24 => "pxr24",
+            B44 => "b44",
+            B44A => "b44a",
+            DWAA(_) => "dwaa",
+            DWAB(_) => "dwab",
+        }
+    }
+
+    /// Find the compression method with the specified short name, ignoring ASCII case.
+    /// The DWA compression methods are returned without a compression level.
+    /// Returns `None` if the name is unknown.
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        use self::Compression::*;
+
+        [ Uncompressed, RLE, ZIP1, ZIP16, PIZ, PXR24, B44, B44A, DWAA(None), DWAB(None) ].iter()
+            .find(|compression| compression.name().as_bytes().eq_ignore_ascii_case(name))
+            .copied()
+    }
+
     /// Most compression methods will reconstruct the exact pixel bytes,
     /// but some might replace NaN with zeroes.
     pub fn supports_nan(self) -> bool {
@@ -299,21 +337,39 @@ impl Compression {
         }
     }
 
+    /// Check whether this compression method is meaningful for the specified channels.
+    /// The lossy `DWAA` and `DWAB` methods only compress float channels,
+    /// so they are rejected for channel lists that contain only `u32` channels.
+    /// All other methods accept any channels, passing through the samples they do not compress specially.
+    pub fn supports(self, channels: &ChannelList) -> UnitResult {
+        use self::Compression::*;
+
+        let has_float_channel = channels.list.iter()
+            .any(|channel| channel.sample_type != SampleType::U32);
+
+        match self {
+            DWAA(_) | DWAB(_) if !has_float_channel =>
+                Err(Error::invalid(format!("{} compression requires at least one float channel", self))),
+
+            _ => Ok(()),
+        }
+    }
+
 }
 
 // see https://github.com/AcademySoftwareFoundation/openexr/blob/6a9f8af6e89547bcd370ae3cec2b12849eee0b54/OpenEXR/IlmImf/ImfMisc.cpp#L1456-L1541
 
 #[allow(unused)] // allows the extra parameters to be unused
 fn convert_current_to_little_endian(mut bytes: ByteVec, channels: &ChannelList, rectangle: IntegerBounds) -> ByteVec {
-    #[cfg(target = "big_endian")]
-    reverse_block_endianness(&mut byte_vec, channels, rectangle);
+    #[cfg(target_endian = "big")]
+    reverse_block_endianness(&mut bytes, channels, rectangle);
 
     bytes
 }
 
 #[allow(unused)] // allows the extra parameters to be unused
 fn convert_little_endian_to_current(mut bytes: ByteVec, channels: &ChannelList, rectangle: IntegerBounds) -> ByteVec {
-    #[cfg(target = "big_endian")]
+    #[cfg(target_endian = "big")]
     reverse_block_endianness(&mut bytes, channels, rectangle);
 
     bytes
@@ -650,6 +706,114 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn reversed_big_endian_block_is_little_endian(){
+        let a32 = ChannelDescription::new("A", SampleType::F32, true);
+        let y16 = ChannelDescription::new("Y", SampleType::F16, true);
+        let channels = ChannelList::new(smallvec![ a32, y16 ]);
+
+        let f32_values = [ 23582740683_f32, -3.5_f32 ];
+        let f16_values = [ 2.5_f32.to_f16(), -0.125_f32.to_f16() ];
+
+        // simulate a block in native big endian byte order, as created on a big endian host
+        let mut big_endian: ByteVec = f32_values.iter().flat_map(|value| value.to_be_bytes())
+            .chain(f16_values.iter().flat_map(|value| value.to_be_bytes()))
+            .collect();
+
+        let little_endian: ByteVec = f32_values.iter().flat_map(|value| value.to_le_bytes())
+            .chain(f16_values.iter().flat_map(|value| value.to_le_bytes()))
+            .collect();
+
+        reverse_block_endianness(&mut big_endian, &channels, IntegerBounds::from_dimensions((2, 1)));
+        assert_eq!(big_endian, little_endian, "big endian block was not converted to little endian");
+    }
+
+    #[test]
+    fn scan_lines_per_block_and_lossiness_match_specification(){
+        let expected = [
+            (Compression::Uncompressed, 1, false),
+            (Compression::RLE, 1, false),
+            (Compression::ZIP1, 1, false),
+            (Compression::ZIP16, 16, false),
+            (Compression::PIZ, 32, false),
+            (Compression::PXR24, 16, true),
+            (Compression::B44, 32, true),
+            (Compression::B44A, 32, true),
+            (Compression::DWAA(None), 32, true),
+            (Compression::DWAB(None), 256, true),
+        ];
+
+        for &(compression, scan_lines, lossy) in &expected {
+            assert_eq!(compression.scan_lines_per_block(), scan_lines, "scan lines per block of {}", compression);
+            assert_eq!(compression.is_lossy(), lossy, "lossiness of {}", compression);
+        }
+    }
+
+    #[test]
+    fn dwa_rejects_integer_channels_only(){
+        let ids = ChannelList::new(smallvec![ ChannelDescription::named("id", SampleType::U32) ]);
+        let mixed = ChannelList::new(smallvec![
+            ChannelDescription::named("Y", SampleType::F16),
+            ChannelDescription::named("id", SampleType::U32),
+        ]);
+
+        for &compression in &[ Compression::DWAA(None), Compression::DWAB(Some(45.0)) ] {
+            match compression.supports(&ids) {
+                Err(Error::Invalid(message)) => assert!(message.contains("float channel"), "{}", message),
+                other => panic!("{} not rejected for integer channels: {:?}", compression, other),
+            }
+
+            assert!(compression.supports(&mixed).is_ok());
+        }
+
+        for &compression in &[ Compression::Uncompressed, Compression::ZIP16, Compression::PXR24, Compression::B44 ] {
+            assert!(compression.supports(&ids).is_ok());
+        }
+    }
+
+    #[test]
+    fn reject_chunks_of_the_wrong_size(){
+        let header = Header::new(
+            "layer".into(), (16, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F32) ]
+        );
+
+        // the chunk contains only 15 of the 16 pixels in the line
+        let line = IntegerBounds::new((0, 0), (16, 1));
+        let short_line = IntegerBounds::new((0, 0), (15, 1));
+        let pixels: ByteVec = vec![ 0; short_line.size.area() * size_of::<f32>() ];
+
+        for &compression in &[ Compression::Uncompressed, Compression::RLE, Compression::ZIP1 ] {
+            let compressed = compression.compress_image_section(&header, pixels.clone(), short_line).unwrap();
+
+            for &pedantic in &[ false, true ] {
+                match compression.decompress_image_section(&header, compressed.clone(), line, pedantic) {
+                    Err(Error::Invalid(message)) => assert_eq!(message, "chunk size mismatch", "{}", compression),
+                    other => panic!("{} chunk of the wrong size not rejected: {:?}", compression, other.map(|_| ())),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compression_names_roundtrip(){
+        let all = [
+            Compression::Uncompressed, Compression::RLE, Compression::ZIP1, Compression::ZIP16,
+            Compression::PIZ, Compression::PXR24, Compression::B44, Compression::B44A,
+            Compression::DWAA(None), Compression::DWAB(None),
+        ];
+
+        for &compression in &all {
+            assert_eq!(Compression::from_name(compression.name().as_bytes()), Some(compression));
+        }
+
+        assert_eq!(Compression::from_name(b"zip"), Some(Compression::ZIP16));
+        assert_eq!(Compression::from_name(b"ZIPS"), Some(Compression::ZIP1));
+        assert_eq!(Compression::DWAA(Some(45.0)).name(), "dwaa");
+        assert_eq!(Compression::from_name(b"zip16"), None);
+        assert_eq!(Compression::from_name(b""), None);
+    }
+
     fn roundtrip_convert_endianness(
         current_endian: ByteVec, channels: &ChannelList, rectangle: IntegerBounds
     ){
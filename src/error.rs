@@ -26,11 +26,11 @@ pub type UnitResult = Result<()>;
 #[derive(Debug)]
 pub enum Error {
 
-    /// Reading or Writing the file has been aborted by the caller.
+    /// Reading the file has been cancelled by the caller.
     /// This error will never be triggered by this crate itself,
     /// only by users of this library.
-    /// It exists to be returned from a progress callback.
-    Aborted, // FIXME remove?? is not used really?
+    /// It is returned when a progress callback returns `ControlFlow::Break`.
+    Cancelled,
 
     /// The contents of the file are not supported by
     /// this specific implementation of open exr,
@@ -60,11 +60,14 @@ impl Error {
     }
 }
 
+/// The message of the `Invalid` error that replaces `ErrorKind::UnexpectedEof` errors.
+pub(crate) const MISSING_BYTES: &str = "reference to missing bytes";
+
 /// Enable using the `?` operator on `std::io::Result`.
 impl From<IoError> for Error {
     fn from(error: IoError) -> Self {
         if error.kind() == ErrorKind::UnexpectedEof {
-            Error::invalid("reference to missing bytes")
+            Error::invalid(MISSING_BYTES)
         }
         else {
             Error::Io(error)
@@ -94,7 +97,7 @@ impl fmt::Display for Error {
             Error::Io(err) => err.fmt(formatter),
             Error::NotSupported(message) => write!(formatter, "not supported: {}", message),
             Error::Invalid(message) => write!(formatter, "invalid: {}", message),
-            Error::Aborted => write!(formatter, "cancelled"),
+            Error::Cancelled => write!(formatter, "cancelled"),
         }
     }
 }
@@ -111,6 +114,14 @@ pub(crate) fn usize_to_u16(value: usize) -> Result<u16> {
     Ok(u16::try_from(value)?)
 }
 
+/// Return error on invalid range.
+/// Use this instead of `u64_to_usize` for values read from a file,
+/// which may not fit into the address space of 32-bit systems.
+#[inline]
+pub(crate) fn u64_to_usize_checked(value: u64, error_message: &'static str) -> Result<usize> {
+    usize::try_from(value).map_err(|_| Error::invalid(error_message))
+}
+
 /// Panic on overflow.
 #[inline]
 pub(crate) fn u64_to_usize(value: u64) -> usize {
@@ -134,3 +145,32 @@ pub(crate) fn usize_to_i32(value: usize) -> i32 {
 pub(crate) fn usize_to_u64(value: usize) -> u64 {
     u64::try_from(value).expect("(usize as u64) overflowed")
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checked_conversions_at_boundaries() {
+        assert_eq!(i32_to_usize(0, "zero").unwrap(), 0);
+        assert_eq!(i32_to_usize(i32::MAX, "max").unwrap(), i32::MAX as usize);
+
+        match i32_to_usize(-1, "negative") {
+            Err(Error::Invalid(message)) => assert_eq!(message, "negative"),
+            other => panic!("negative value not rejected: {:?}", other),
+        }
+
+        assert!(i32_to_usize(i32::MIN, "min").is_err());
+
+        assert_eq!(usize_to_u16(u16::MAX as usize).unwrap(), u16::MAX);
+        assert!(usize_to_u16(u16::MAX as usize + 1).is_err());
+
+        assert_eq!(u64_to_usize_checked(0, "zero").unwrap(), 0);
+        assert_eq!(u64_to_usize_checked(usize::MAX as u64, "max").unwrap(), usize::MAX);
+
+        let exceeds_address_space = u64_to_usize_checked(u64::MAX, "too large");
+        if usize::try_from(u64::MAX).is_ok() { assert_eq!(exceeds_address_space.unwrap(), usize::MAX); }
+        else { assert!(exceeds_address_space.is_err()); }
+    }
+}
@@ -34,6 +34,7 @@ pub mod recursive;
 use crate::meta::header::{ImageAttributes, LayerAttributes};
 use crate::meta::attribute::{Text, LineOrder};
 use half::f16;
+use half::slice::HalfFloatSliceExt;
 use crate::math::{Vec2, RoundingMode};
 use crate::compression::Compression;
 use smallvec::{SmallVec};
@@ -168,8 +169,9 @@ pub struct SpecificChannels<Pixels, ChannelsDescription> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct AnyChannels<Samples> {
 
-    /// This list must be sorted alphabetically, by channel name.
-    /// Use `AnyChannels::sorted` for automatic sorting.
+    /// This list should be sorted alphabetically, by channel name.
+    /// Use `AnyChannels::sort` for automatic sorting.
+    /// When writing, unsorted channels are written in alphabetical order.
     pub list: SmallVec<[AnyChannel<Samples>; 4]>
 }
 
@@ -508,6 +510,42 @@ impl Layer<AnyChannels<FlatSamples>> {
             position
         }
     }
+
+    /// Whether both layers have the same size and the same channels,
+    /// and all samples differ by at most `epsilon`. Ignores attributes and encoding.
+    /// See `FlatSamples::approx_eq` for details.
+    pub fn pixels_approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.size == other.size
+            && self.channel_data.list.len() == other.channel_data.list.len()
+            && self.channel_data.list.iter().zip(&other.channel_data.list).all(|(own, other)| {
+                own.name == other.name && own.sample_data.approx_eq(&other.sample_data, epsilon)
+            })
+    }
+
+    /// Whether both layers have the same size, the same channels, and the same samples.
+    /// Ignores attributes and encoding. Any two NaN samples are considered equal.
+    pub fn pixels_eq(&self, other: &Self) -> bool {
+        self.pixels_approx_eq(other, 0.0)
+    }
+}
+
+impl Image<Layers<AnyChannels<FlatSamples>>> {
+
+    /// Whether both images contain the same number of layers,
+    /// and each layer has approximately the same pixels as the corresponding other layer.
+    /// See `Layer::pixels_approx_eq` for details.
+    pub fn pixels_approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.layer_data.len() == other.layer_data.len()
+            && self.layer_data.iter().zip(&other.layer_data)
+                .all(|(own, other)| own.pixels_approx_eq(other, epsilon))
+    }
+
+    /// Whether both images contain the same number of layers,
+    /// and each layer has the same pixels as the corresponding other layer.
+    /// See `Layer::pixels_eq` for details.
+    pub fn pixels_eq(&self, other: &Self) -> bool {
+        self.pixels_approx_eq(other, 0.0)
+    }
 }
 
 /// Iterate over all channels of a single pixel in the image
@@ -652,8 +690,55 @@ impl<Samples> RipMaps<Samples> {
     }
 }
 
+/// Specifies how `u32` samples are converted to `f32` values,
+/// as there is no single obvious conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum U32ToF32 {
+
+    /// Convert the integer to the closest float, such that `7_u32` becomes `7.0_f32`.
+    /// Large integers may lose precision.
+    Numeric,
+
+    /// Divide the integer by `u32::MAX`, mapping the full integer range to `0.0 ..= 1.0`.
+    Normalized,
+
+    /// Reinterpret the bits of the integer as a float,
+    /// for channels that store float data in `u32` samples.
+    BitCast,
+}
+
 impl FlatSamples {
 
+    /// Create the specified number of samples of the specified type, all set to zero.
+    pub fn zeroed(sample_type: SampleType, len: usize) -> Self {
+        match sample_type {
+            SampleType::F16 => FlatSamples::F16(vec![ f16::ZERO; len ]),
+            SampleType::F32 => FlatSamples::F32(vec![ 0.0; len ]),
+            SampleType::U32 => FlatSamples::U32(vec![ 0; len ]),
+        }
+    }
+
+    /// Convert all samples of this channel to a new vector of `f32` values.
+    /// Half-precision samples are widened exactly, using batched conversion.
+    /// Integer samples are converted as specified by the `u32_conversion`.
+    pub fn to_f32_vec(&self, u32_conversion: U32ToF32) -> Vec<f32> {
+        match self {
+            FlatSamples::F16(samples) => {
+                let mut result = vec![0.0_f32; samples.len()];
+                samples.as_slice().convert_to_f32_slice(&mut result);
+                result
+            },
+
+            FlatSamples::F32(samples) => samples.clone(),
+
+            FlatSamples::U32(samples) => match u32_conversion {
+                U32ToF32::Numeric => samples.iter().map(|&sample| sample as f32).collect(),
+                U32ToF32::Normalized => samples.iter().map(|&sample| (f64::from(sample) / f64::from(u32::MAX)) as f32).collect(),
+                U32ToF32::BitCast => samples.iter().map(|&sample| f32::from_bits(sample)).collect(),
+            },
+        }
+    }
+
     /// The number of samples in the image. Should be the width times the height.
     /// Might vary when subsampling is used.
     pub fn len(&self) -> usize {
@@ -678,6 +763,26 @@ impl FlatSamples {
         (0..self.len()).map(move |index| self.value_by_flat_index(index))
     }
 
+    /// Whether both storages have the same sample type and length,
+    /// and all samples differ by at most `epsilon`.
+    /// Integer samples must be exactly equal, and any two NaN samples are considered equal.
+    pub fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        let float_eq = |own: f32, other: f32| {
+            own == other || (own.is_nan() && other.is_nan()) || (own - other).abs() <= epsilon
+        };
+
+        match (self, other) {
+            (FlatSamples::F16(own), FlatSamples::F16(other)) => own.len() == other.len()
+                && own.iter().zip(other).all(|(own, other)| float_eq(own.to_f32(), other.to_f32())),
+
+            (FlatSamples::F32(own), FlatSamples::F32(other)) => own.len() == other.len()
+                && own.iter().zip(other).all(|(&own, &other)| float_eq(own, other)),
+
+            (FlatSamples::U32(own), FlatSamples::U32(other)) => own == other,
+            _ => false,
+        }
+    }
+
     /// Lookup a single value, by flat index.
     /// The flat index can be obtained using `Vec2::flatten_for_width`
     /// which computes the index in a flattened array of pixel rows.
@@ -767,6 +872,13 @@ impl<'s, LayerData: 's> Image<LayerData> where LayerData: WritableLayers<'s> {
     pub fn new(image_attributes: ImageAttributes, layer_data: LayerData) -> Self {
         Image { attributes: image_attributes, layer_data }
     }
+
+    /// Set the display window of the image, which is written independently of the data windows of the layers.
+    /// Use this to place the layers inside a larger frame, for example when compositing.
+    pub fn with_display_window(mut self, display_window: IntegerBounds) -> Self {
+        self.attributes.display_window = display_window;
+        self
+    }
 }
 
 // explorable constructor alias
@@ -798,6 +910,36 @@ impl<'s, ChannelData:'s> Image<Layer<ChannelData>> where ChannelData: WritableCh
     }
 }
 
+impl Image<Layer<AnyChannels<FlatSamples>>> {
+
+    /// Create a single-layer image with the specified channels, where every sample is zero.
+    /// The channels are specified by name and sample type, and will be sorted alphabetically.
+    /// Uses empty attributes and fast compression.
+    /// Fill the samples of `layer_data.channel_data` before writing the image.
+    pub fn zeroed<Name: Into<Text>>(size: impl Into<Vec2<usize>>, channels: impl IntoIterator<Item=(Name, SampleType)>) -> Self {
+        let size = size.into();
+
+        let channels = channels.into_iter()
+            .map(|(name, sample_type)| AnyChannel::new(name, FlatSamples::zeroed(sample_type, size.area())))
+            .collect();
+
+        Self::from_channels(size, AnyChannels::sort(channels))
+    }
+}
+
+
+/// Apply the sRGB transfer function to a linear value, as specified by IEC 61966-2-1.
+/// Values between `0.0` and `1.0` are mapped to encoded values between `0.0` and `1.0`.
+/// Negative values are mirrored, such that the function is symmetric around zero.
+pub fn linear_to_srgb(linear: f32) -> f32 {
+    let magnitude = linear.abs();
+
+    let encoded = if magnitude <= 0.003_130_8 { magnitude * 12.92 }
+        else { 1.055 * magnitude.powf(1.0 / 2.4) - 0.055 };
+
+    encoded.copysign(linear)
+}
+
 
 impl Image<NoneMore> {
 
@@ -1324,3 +1466,129 @@ pub mod validate_results {
 }
 
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_linear_rgba_as_srgb() {
+        use crate::prelude::*;
+        use crate::image::pixel_vec::PixelVec;
+
+        let size = Vec2(2, 1);
+        let mut pixels = vec![ (0.5_f32, 0.0_f32, 1.0_f32, 0.5_f32); size.area() ];
+        pixels[1].0 = 0.002;
+
+        let write_rgba = |red_is_perceptual: bool| {
+            let mut image = Image::from_channels(size, SpecificChannels::rgba(PixelVec::new(size, pixels.clone())));
+            image.layer_data.channel_data.channels.0.quantize_linearly = red_is_perceptual;
+            image.write().to_vec().unwrap()
+        };
+
+        let read_rgba = |bytes: &[u8], srgb_encoded: bool| {
+            let channels = read().no_deep_data().largest_resolution_level()
+                .rgba_channels(PixelVec::<(f32, f32, f32, f32)>::constructor, PixelVec::set_pixel);
+
+            let channels = if srgb_encoded { channels.srgb_encoded() } else { channels };
+            channels.first_valid_layer().all_attributes()
+                .from_buffered(std::io::Cursor::new(bytes)).unwrap()
+                .layer_data.channel_data.pixels.pixels
+        };
+
+        let linear = write_rgba(false);
+        assert_eq!(read_rgba(&linear, false), pixels);
+
+        let encoded = read_rgba(&linear, true);
+        let (red, green, blue, alpha) = encoded[0];
+        assert!((red - 0.735_357).abs() < 1.0e-5, "{}", red);
+        assert!((blue - 1.0).abs() < 1.0e-6, "{}", blue);
+        assert_eq!((green, alpha), (0.0, 0.5));
+        assert!((encoded[1].0 - 0.002 * 12.92).abs() < 1.0e-6);
+
+        // perceptually linear channels are already encoded
+        let perceptual = read_rgba(&write_rgba(true), true);
+        assert_eq!(perceptual[0].0, 0.5);
+        assert!((perceptual[0].2 - 1.0).abs() < 1.0e-6);
+
+        // the option is applied per channel, also when converting to other sample types
+        let half = read().no_deep_data().largest_resolution_level()
+            .rgb_channels(PixelVec::<(f16, f16, f16)>::constructor, PixelVec::set_pixel)
+            .srgb_encoded().first_valid_layer().all_attributes()
+            .from_buffered(std::io::Cursor::new(&linear)).unwrap();
+
+        assert_eq!(half.layer_data.channel_data.pixels.pixels[0].0, f16::from_f32(linear_to_srgb(0.5)));
+
+        assert_eq!(linear_to_srgb(-0.5), -linear_to_srgb(0.5));
+    }
+
+    #[test]
+    fn compare_pixels_of_flat_images() {
+        let image = |alpha: Vec<f32>| Image::from_layers(
+            ImageAttributes::with_size((2, 2)),
+            smallvec![ Layer::new(
+                (2, 2), LayerAttributes::named("main"), Encoding::FAST_LOSSLESS,
+                AnyChannels::sort(smallvec![
+                    AnyChannel::new("A", FlatSamples::F32(alpha)),
+                    AnyChannel::new("id", FlatSamples::U32(vec![ 1, 2, 3, 4 ])),
+                ])
+            ) ]
+        );
+
+        let original = image(vec![ 0.0, 0.5, f32::NAN, 1.0 ]);
+        assert!(original.pixels_eq(&original.clone()));
+
+        let one_pixel_different = image(vec![ 0.0, 0.5, f32::NAN, 1.001 ]);
+        assert!(!original.pixels_eq(&one_pixel_different));
+        assert!(original.pixels_approx_eq(&one_pixel_different, 0.01));
+        assert!(!original.pixels_approx_eq(&one_pixel_different, 0.0001));
+
+        let mut different_id = original.clone();
+        different_id.layer_data[0].channel_data.list[1].sample_data = FlatSamples::U32(vec![ 1, 2, 3, 5 ]);
+        assert!(!original.pixels_approx_eq(&different_id, 100.0));
+
+        let mut different_type = original.clone();
+        different_type.layer_data[0].channel_data.list[1].sample_data = FlatSamples::F32(vec![ 1.0, 2.0, 3.0, 4.0 ]);
+        assert!(!original.pixels_approx_eq(&different_type, 100.0));
+    }
+
+    #[test]
+    fn write_zeroed_rgba_image() {
+        use crate::prelude::*;
+
+        let mut image = Image::zeroed((64, 64), vec![
+            ("R", SampleType::F16), ("G", SampleType::F16), ("B", SampleType::F16), ("A", SampleType::F32),
+        ]);
+
+        assert_eq!(image.attributes.display_window, IntegerBounds::from_dimensions((64, 64)));
+        assert_eq!(image.layer_data.size, Vec2(64, 64));
+
+        let channels = &mut image.layer_data.channel_data.list;
+        let names: Vec<Text> = channels.iter().map(|channel| channel.name.clone()).collect();
+        assert_eq!(names, vec![ Text::from("A"), Text::from("B"), Text::from("G"), Text::from("R") ]);
+
+        assert_eq!(channels[0].sample_data, FlatSamples::F32(vec![ 0.0; 64 * 64 ]));
+        assert_eq!(channels[3].sample_data, FlatSamples::F16(vec![ f16::ZERO; 64 * 64 ]));
+
+        if let FlatSamples::F32(alpha) = &mut channels[0].sample_data { alpha[5] = 1.0; }
+
+        let mut bytes = Vec::new();
+        image.write().non_parallel().to_buffered(std::io::Cursor::new(&mut bytes)).unwrap();
+
+        let read_back = read().no_deep_data().largest_resolution_level().all_channels()
+            .first_valid_layer().all_attributes().from_buffered(std::io::Cursor::new(&bytes)).unwrap();
+
+        assert_eq!(read_back.layer_data.size, Vec2(64, 64));
+        assert_eq!(read_back.layer_data.channel_data.list, image.layer_data.channel_data.list);
+    }
+
+    #[test]
+    fn flat_samples_to_f32_vec() {
+        let halfs = FlatSamples::F16(vec![ f16::from_f32(0.5), f16::from_f32(2.0), f16::from_f32(-0.25) ]);
+        assert_eq!(halfs.to_f32_vec(U32ToF32::Numeric), vec![ 0.5_f32, 2.0, -0.25 ]);
+
+        let uints = FlatSamples::U32(vec![ 0, 7, u32::MAX, 1.5_f32.to_bits() ]);
+        assert_eq!(uints.to_f32_vec(U32ToF32::Numeric), vec![ 0.0, 7.0, u32::MAX as f32, 1.5_f32.to_bits() as f32 ]);
+        assert_eq!(uints.to_f32_vec(U32ToF32::Normalized)[..3], [ 0.0, 7.0 / u32::MAX as f32, 1.0 ]);
+        assert_eq!(uints.to_f32_vec(U32ToF32::BitCast)[3], 1.5);
+    }
+}
@@ -40,6 +40,16 @@ impl<Pixel> PixelVec<Pixel> {
         PixelVec { resolution, pixels: vec![Pixel::default(); resolution.area()] }
     }
 
+    /// Create a new flattened pixel storage, filled with default pixels,
+    /// reusing the allocation of a vector that is no longer needed.
+    /// When reading many images of the same size, pass the pixels of the previous image
+    /// to avoid allocating a new vector for each image.
+    pub fn reusing(mut pixels: Vec<Pixel>, resolution: Vec2<usize>) -> Self where Pixel: Default + Clone {
+        pixels.clear();
+        pixels.resize(resolution.area(), Pixel::default());
+        PixelVec { resolution, pixels }
+    }
+
     /// Examine a pixel of a `PixelVec<T>` image.
     /// Can usually be used as a function reference instead of calling it directly.
     #[inline]
@@ -10,7 +10,18 @@ use std::path::Path;
 use std::io::{Read, BufReader};
 use std::io::Seek;
 use crate::meta::MetaData;
-use crate::block::reader::ChunksReader;
+use crate::meta::attribute::AttributeTypeRegistry;
+use crate::block::reader::{ChunksReader, ProgressCallback, Cancellable};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+#[cfg(feature = "async")]
+use {
+    crate::error::Error,
+    crate::io::{Data, PartiallyLoadedBytes},
+    std::io::SeekFrom,
+    tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt},
+};
 
 /// Specify whether to read the image in parallel,
 /// whether to use pedantic error handling,
@@ -21,15 +32,21 @@ pub struct ReadImage<OnProgress, ReadLayers> {
     read_layers: ReadLayers,
     pedantic: bool,
     parallel: bool,
+    reconstruct_offset_tables: bool,
+    skip_unknown_parts: bool,
+    attribute_types: Option<Arc<AttributeTypeRegistry>>,
 }
 
-impl<F, L> ReadImage<F, L> where F: FnMut(f64)
+impl<F, L> ReadImage<F, L> where F: ProgressCallback
 {
     /// Uses relaxed error handling and parallel decompression.
     pub fn new(read_layers: L, on_progress: F) -> Self {
         Self {
             on_progress, read_layers,
             pedantic: false,
+            reconstruct_offset_tables: false,
+            skip_unknown_parts: false,
+            attribute_types: None,
             #[cfg(not(feature = "rayon"))]
             parallel: false,
             #[cfg(feature = "rayon")]
@@ -55,6 +72,26 @@ impl<F, L> ReadImage<F, L> where F: FnMut(f64)
     /// This might be slower but uses less memory and less synchronization.
     pub fn non_parallel(self) -> Self { Self { parallel: false, ..self } }
 
+    /// Specify that invalid offset tables should be rebuilt instead of resulting in an error.
+    /// This can recover files that were not written completely.
+    /// The tables are rebuilt by reading all chunks sequentially, as each chunk contains its own size.
+    pub fn reconstruct_offset_tables(self) -> Self { Self { reconstruct_offset_tables: true, ..self } }
+
+    /// Specify that parts of multi-part files with an unknown type should be skipped instead of resulting in an error.
+    /// This allows reading files that contain part types defined by future versions of the specification.
+    /// The skipped parts are not contained in the resulting image.
+    /// To find out which parts have been skipped, create a `Reader` using
+    /// `exr::block::reader::Reader::read_from_buffered_skipping_unknown_parts`,
+    /// inspect its `unknown_parts()`, and then pass it to `from_chunks`.
+    pub fn skip_unknown_parts(self) -> Self { Self { skip_unknown_parts: true, ..self } }
+
+    /// Specify how to parse the attribute values in the headers.
+    /// Attributes with types that are not contained in the registry are read as `AttributeValue::Custom`.
+    /// By default, all types defined by the specification are parsed.
+    pub fn with_attribute_types(self, registry: AttributeTypeRegistry) -> Self {
+        Self { attribute_types: Some(Arc::new(registry)), ..self }
+    }
+
     /// Specify a function to be called regularly throughout the loading process.
     /// Replaces all previously specified progress functions in this reader.
     pub fn on_progress<OnProgress>(self, on_progress: OnProgress) -> ReadImage<OnProgress, L>
@@ -64,7 +101,28 @@ impl<F, L> ReadImage<F, L> where F: FnMut(f64)
             on_progress,
             read_layers: self.read_layers,
             pedantic: self.pedantic,
-            parallel: self.parallel
+            parallel: self.parallel,
+            reconstruct_offset_tables: self.reconstruct_offset_tables,
+            skip_unknown_parts: self.skip_unknown_parts,
+            attribute_types: self.attribute_types,
+        }
+    }
+
+    /// Specify a function to be called regularly throughout the loading process,
+    /// which can cancel the loading process by returning `ControlFlow::Break`.
+    /// In that case, reading returns `Error::Cancelled`.
+    /// Replaces all previously specified progress functions in this reader.
+    pub fn on_progress_cancellable<OnProgress>(self, on_progress: OnProgress) -> ReadImage<Cancellable<OnProgress>, L>
+        where OnProgress: FnMut(f64) -> ControlFlow<()>
+    {
+        ReadImage {
+            on_progress: Cancellable(on_progress),
+            read_layers: self.read_layers,
+            pedantic: self.pedantic,
+            parallel: self.parallel,
+            reconstruct_offset_tables: self.reconstruct_offset_tables,
+            skip_unknown_parts: self.skip_unknown_parts,
+            attribute_types: self.attribute_types,
         }
     }
 
@@ -98,10 +156,104 @@ impl<F, L> ReadImage<F, L> where F: FnMut(f64)
     pub fn from_buffered<Layers>(self, buffered: impl Read + Seek) -> Result<Image<Layers>>
         where for<'s> L: ReadLayers<'s, Layers = Layers>
     {
-        let chunks = crate::block::read(buffered, self.pedantic)?;
+        let chunks = self.read_chunks_reader(buffered)?;
+        self.from_chunks(chunks)
+    }
+
+    /// Read the exr image from an asynchronous byte source, for example a `tokio::fs::File`.
+    /// Only loads the meta data, the offset tables, and the chunks required by this reader,
+    /// then decodes them just like [`ReadImage::from_buffered`] does.
+    /// The pixels are decompressed after loading, not asynchronously.
+    /// Requires the `async` feature.
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub async fn read_async<Layers>(self, mut read: impl AsyncRead + AsyncSeek + Unpin) -> Result<Image<Layers>>
+        where for<'s> L: ReadLayers<'s, Layers = Layers>
+    {
+        let file_size = read.seek(SeekFrom::End(0)).await?;
+        let mut bytes = PartiallyLoadedBytes::new(file_size);
+
+        // the size of the meta data is not known in advance, so load more bytes until all headers can be parsed.
+        // bytes that have not been loaded yet may also be reported as other invalid data, such as an invalid attribute size
+        let mut loaded_size = 0;
+        let chunk_count = loop {
+            let size = (loaded_size * 2).max(4096).min(file_size);
+            bytes.load(&mut read, loaded_size .. size).await?;
+            loaded_size = size;
+
+            bytes.seek(SeekFrom::Start(0))?;
+            match self.read_chunks_reader(&mut bytes) {
+                Err(Error::Invalid(_)) if loaded_size < file_size => continue,
+                chunks => {
+                    let chunks = chunks?;
+
+                    break chunks.headers().iter().map(|header| header.chunk_count)
+                        .chain(chunks.unknown_parts().iter().map(|part| part.chunk_count))
+                        .sum::<usize>();
+                }
+            }
+        };
+
+        // the offset tables of all parts directly follow the headers
+        let offset_tables_start = bytes.position();
+        let offset_tables_end = (offset_tables_start + chunk_count as u64 * u64::BYTE_SIZE as u64).min(file_size);
+        bytes.load(&mut read, offset_tables_start .. offset_tables_end).await?;
+
+        if self.reconstruct_offset_tables {
+            // the offset tables may be broken, and the chunks are searched for instead
+            bytes.load(&mut read, offset_tables_end .. file_size).await?;
+        }
+        else {
+            bytes.seek(SeekFrom::Start(offset_tables_start))?;
+            let mut chunk_starts = (0 .. chunk_count).map(|_| u64::read(&mut bytes)).collect::<Result<Vec<u64>>>()?;
+            chunk_starts.sort_unstable();
+
+            bytes.seek(SeekFrom::Start(0))?;
+            let required_chunk_starts = {
+                let chunks = self.read_chunks_reader(&mut bytes)?;
+                let layers_reader = self.read_layers.create_layers_reader(chunks.headers())?;
+                let image_collector = ImageWithAttributesReader::new(chunks.headers(), layers_reader)?;
+
+                let filtered_chunks = chunks.filter_chunks(self.pedantic, |meta, tile, block| {
+                    image_collector.filter_block(meta, tile, block)
+                })?;
+
+                filtered_chunks.remaining_chunk_offsets().to_vec()
+            };
+
+            // each chunk ends where the next chunk in the file starts,
+            // and small gaps between the required chunks are read through instead of seeking over them
+            let mut ranges = vec![ offset_tables_start .. offset_tables_end ];
+            for start in required_chunk_starts {
+                let next_chunk_index = chunk_starts.partition_point(|&chunk_start| chunk_start <= start);
+                let end = chunk_starts.get(next_chunk_index).map_or(file_size, |&next| next.min(file_size));
+
+                match ranges.last_mut() {
+                    Some(previous) if start >= previous.start && start < previous.end + 16 => previous.end = previous.end.max(end),
+                    _ => ranges.push(start .. end),
+                }
+            }
+
+            for range in ranges {
+                bytes.load(&mut read, range).await?;
+            }
+        }
+
+        bytes.seek(SeekFrom::Start(0))?;
+        let chunks = self.read_chunks_reader(bytes)?;
         self.from_chunks(chunks)
     }
 
+    /// Read the meta data from the byte source, using the attribute types and unknown part handling of this reader.
+    fn read_chunks_reader<R: Read + Seek>(&self, buffered: R) -> Result<crate::block::reader::Reader<R>> {
+        use crate::block::reader::Reader;
+        let registry = self.attribute_types.as_deref();
+
+        if self.skip_unknown_parts { Reader::read_from_buffered_skipping_unknown_parts(buffered, self.pedantic, registry) }
+        else if let Some(registry) = registry { Reader::read_from_buffered_with_registry(buffered, self.pedantic, registry) }
+        else { crate::block::read(buffered, self.pedantic) }
+    }
+
     /// Read the exr image from an initialized chunks reader
     /// that has already extracted the meta data from the file.
     /// Use [`ReadImage::read_from_file`] instead, if you have a file path.
@@ -111,7 +263,11 @@ impl<F, L> ReadImage<F, L> where F: FnMut(f64)
     pub fn from_chunks<Layers>(mut self, chunks_reader: crate::block::reader::Reader<impl Read + Seek>) -> Result<Image<Layers>>
         where for<'s> L: ReadLayers<'s, Layers = Layers>
     {
-        let Self { pedantic, parallel, ref mut on_progress, ref mut read_layers } = self;
+        let Self { pedantic, parallel, reconstruct_offset_tables, on_progress, ref mut read_layers, .. } = self;
+
+        let chunks_reader =
+            if reconstruct_offset_tables { chunks_reader.reconstruct_offset_tables() }
+            else { chunks_reader };
 
         let layers_reader = read_layers.create_layers_reader(chunks_reader.headers())?;
         let mut image_collector = ImageWithAttributesReader::new(chunks_reader.headers(), layers_reader)?;
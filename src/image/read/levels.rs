@@ -56,8 +56,8 @@ impl<DeepOrFlatSamples> ReadLargestLevel<DeepOrFlatSamples> {
             Set: Fn(&mut Pixels, Vec2<usize>, (R,G,B,A)),
     {
         self.specific_channels()
-            .required("R").required("G").required("B")
-            .optional("A", A::from_f32(1.0))
+            .required_by_suffix("R").required_by_suffix("G").required_by_suffix("B")
+            .optional_by_suffix("A", A::from_f32(1.0))
             .collect_pixels(create_pixels, set_pixel)
     }
 
@@ -49,13 +49,21 @@ pub mod levels;
 pub mod samples;
 pub mod specific_channels;
 
-use crate::error::{Result};
+use crate::error::{Error, Result};
 use crate::image::read::samples::{ReadFlatSamples};
 use std::path::Path;
-use crate::image::{AnyImage, AnyChannels, FlatSamples, Image, Layer, FlatImage, PixelLayersImage, RgbaChannels};
+use std::fs::File;
+use std::io::BufReader;
+use crate::image::{AnyImage, AnyChannels, FlatSamples, Image, Layer, FlatImage, PixelLayersImage, RgbaChannels, RgbaImage};
+use crate::image::pixel_vec::PixelVec;
+use half::f16;
 use crate::image::read::image::ReadLayers;
 use crate::image::read::layers::ReadChannels;
 use crate::math::Vec2;
+use crate::meta::{MetaData, magic_number, sequence_end};
+use crate::meta::attribute::{Preview, ChannelList, LazyAttribute, AttributeValue};
+use crate::meta::header::standard_names;
+use crate::io::{PeekRead, Tracking};
 use crate::prelude::{PixelImage};
 use crate::block::samples::FromNativeSample;
 
@@ -100,8 +108,59 @@ pub fn read_first_flat_layer_from_file(path: impl AsRef<Path>) -> Result<Image<L
         .from_file(path)
 }
 
+/// Only read the preview thumbnail of the first layer that contains one.
+/// Parses the meta data only, never touching the pixel data or offset tables,
+/// which is much faster than reading the whole image.
+/// Returns `None` if no layer contains a preview.
+pub fn read_preview_from_file(path: impl AsRef<Path>) -> Result<Option<Preview>> {
+    let meta_data = MetaData::read_from_file(path, false)?;
+
+    Ok(meta_data.headers.into_iter()
+        .find_map(|header| header.own_attributes.preview))
+}
+
+/// Only read the channel list of each layer, for example to find out which render passes a file contains.
+/// Does not parse any other attribute, so missing or invalid attributes other than `channels` are ignored.
+/// Never touches the pixel data or offset tables, which is much faster than reading the whole image.
+/// The channel names and sample types can be found in each `ChannelList`.
+pub fn read_channels_from_file(path: impl AsRef<Path>) -> Result<Vec<ChannelList>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut headers = Vec::new();
+
+    {
+        let mut read = PeekRead::new(Tracking::new(&mut file));
+        let requirements = magic_number::skip_identification_bytes(&mut read)?;
+        let max_name_size = if requirements.has_long_names { 256 } else { 32 };
+
+        if requirements.is_multilayer() {
+            while !sequence_end::has_come(&mut read)? {
+                headers.push(LazyAttribute::read_header(&mut read, max_name_size)?);
+            }
+        }
+        else {
+            headers.push(LazyAttribute::read_header(&mut read, max_name_size)?);
+        }
+    }
+
+    headers.into_iter()
+        .map(|attributes| {
+            let channels = attributes.iter()
+                .find(|attribute| attribute.name.as_slice() == standard_names::CHANNELS)
+                .ok_or(Error::invalid("missing channels attribute"))?;
+
+            match channels.value(&mut file)? {
+                AttributeValue::ChannelList(channels) => Ok(channels),
+                _ => Err(Error::invalid("channels attribute type")),
+            }
+        })
+        .collect()
+}
+
 /// No deep data, no resolution levels, rgba channels, all layers.
 /// If a single layer does not contain rgba data, this method returns an error.
+/// The name of each layer, such as a render pass, can be found in its attributes.
+/// Channels of a single layer are not split by their name prefix, such as `diffuse.R`;
+/// use `ChannelList::grouped_by_layer` to inspect those.
 /// Uses parallel decompression and relaxed error handling.
 /// `Create` and `Set` can be closures, see the examples for more information.
 /// Inspect the source code of this function if you need customization.
@@ -111,6 +170,7 @@ pub fn read_first_flat_layer_from_file(path: impl AsRef<Path>) -> Result<Image<L
 /// The first closure creates an image, and the second closure inserts a single pixel.
 /// The type of the pixel can be defined by the second closure;
 /// it must be a tuple containing four values, each being either `f16`, `f32`, `u32` or `Sample`.
+/// Choose `u32` to read integer channels, such as object ids, exactly, without converting them to `f32`.
 // FIXME Set and Create should not need to be static
 pub fn read_all_rgba_layers_from_file<R,G,B,A, Set:'static, Create:'static, Pixels: 'static>(
     path: impl AsRef<Path>, create: Create, set_pixel: Set
@@ -140,6 +200,7 @@ pub fn read_all_rgba_layers_from_file<R,G,B,A, Set:'static, Create:'static, Pixe
 /// The first closure creates an image, and the second closure inserts a single pixel.
 /// The type of the pixel can be defined by the second closure;
 /// it must be a tuple containing four values, each being either `f16`, `f32`, `u32` or `Sample`.
+/// Choose `u32` to read integer channels, such as object ids, exactly, without converting them to `f32`.
 // FIXME Set and Create should not need to be static
 pub fn read_first_rgba_layer_from_file<R,G,B,A, Set:'static, Create:'static, Pixels: 'static>(
     path: impl AsRef<Path>, create: Create, set_pixel: Set
@@ -159,6 +220,20 @@ pub fn read_first_rgba_layer_from_file<R,G,B,A, Set:'static, Create:'static, Pix
         .from_file(path)
 }
 
+/// No deep data, no resolution levels, rgba channels, choosing the first layer with rgba channels.
+/// Stores each pixel as `[red, green, blue, alpha]` in a flat vector of half floats,
+/// which can be uploaded to the gpu directly.
+/// Samples of `f16` channels are copied exactly, without converting them to `f32` first.
+/// Samples of other channels are converted to `f16`.
+/// The alpha channel will contain the value `1.0` if no alpha channel can be found in the image.
+pub fn read_first_rgba_layer_as_f16(path: impl AsRef<Path>) -> Result<RgbaImage<PixelVec<[f16; 4]>>> {
+    read_first_rgba_layer_from_file(
+        path, PixelVec::constructor,
+        |pixels: &mut PixelVec<[f16; 4]>, position, (r, g, b, a): (f16, f16, f16, f16)|
+            pixels.set_pixel(position, [r, g, b, a])
+    )
+}
+
 
 /// Utilizes the builder pattern to configure an image reader. This is the initial struct.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
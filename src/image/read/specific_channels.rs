@@ -33,7 +33,14 @@ pub trait ReadSpecificChannel: Sized + CheckDuplicates {
     fn required<Sample>(self, channel_name: impl Into<Text>) -> ReadRequiredChannel<Self, Sample> {
         let channel_name = channel_name.into();
         assert!(self.already_contains(&channel_name).not(), "a channel with the name `{}` is already defined", channel_name);
-        ReadRequiredChannel { channel_name, previous_channels: self, px: Default::default() }
+        ReadRequiredChannel { channel_name, match_suffix: false, previous_channels: self, px: Default::default() }
+    }
+
+    /// Like `required`, but if no channel has exactly this name,
+    /// the only channel with this name after its layer prefix, ignoring case, is used instead,
+    /// such that `R` also finds `diffuse.r`.
+    fn required_by_suffix<Sample>(self, channel_name: impl Into<Text>) -> ReadRequiredChannel<Self, Sample> {
+        ReadRequiredChannel { match_suffix: true, .. self.required(channel_name) }
     }
 
     /// Plan to read an additional channel from the image, with the specified name.
@@ -46,7 +53,16 @@ pub trait ReadSpecificChannel: Sized + CheckDuplicates {
     {
         let channel_name = channel_name.into();
         assert!(self.already_contains(&channel_name).not(), "a channel with the name `{}` is already defined", channel_name);
-        ReadOptionalChannel { channel_name, previous_channels: self, default_sample }
+        ReadOptionalChannel { channel_name, match_suffix: false, previous_channels: self, default_sample }
+    }
+
+    /// Like `optional`, but if no channel has exactly this name,
+    /// the only channel with this name after its layer prefix, ignoring case, is used instead,
+    /// such that `A` also finds `diffuse.a`.
+    fn optional_by_suffix<Sample>(self, channel_name: impl Into<Text>, default_sample: Sample)
+        -> ReadOptionalChannel<Self, Sample>
+    {
+        ReadOptionalChannel { match_suffix: true, .. self.optional(channel_name, default_sample) }
     }
 
     /// Using two closures, define how to store the pixels.
@@ -66,7 +82,7 @@ pub trait ReadSpecificChannel: Sized + CheckDuplicates {
             ) -> PixelStorage,
             SetPixel: Fn(&mut PixelStorage, Vec2<usize>, Pixel),
     {
-        CollectPixels { read_channels: self, set_pixel, create_pixels, px: Default::default() }
+        CollectPixels { read_channels: self, set_pixel, create_pixels, srgb_encoded: false, px: Default::default() }
     }
 }
 
@@ -88,6 +104,10 @@ pub trait RecursivePixelReader {
         &self, bytes: &'s[u8], pixels: &mut [FullPixel],
         get_pixel: impl Fn(&mut FullPixel) -> &mut Self::RecursivePixel
     );
+
+    /// Apply the sRGB transfer function to the samples of all channels
+    /// that contain linear floating point values, as specified by `quantize_linearly`.
+    fn encode_srgb(&mut self);
 }
 
 // does not use the generic `Recursive` struct to reduce the number of angle brackets in the public api
@@ -97,6 +117,7 @@ pub trait RecursivePixelReader {
 pub struct ReadOptionalChannel<ReadChannels, Sample> {
     previous_channels: ReadChannels,
     channel_name: Text,
+    match_suffix: bool,
     default_sample: Sample,
 }
 
@@ -107,6 +128,7 @@ pub struct ReadOptionalChannel<ReadChannels, Sample> {
 pub struct ReadRequiredChannel<ReadChannels, Sample> {
     previous_channels: ReadChannels,
     channel_name: Text,
+    match_suffix: bool,
     px: PhantomData<Sample>,
 }
 
@@ -116,9 +138,18 @@ pub struct CollectPixels<ReadChannels, Pixel, PixelStorage, CreatePixels, SetPix
     read_channels: ReadChannels,
     create_pixels: CreatePixels,
     set_pixel: SetPixel,
+    srgb_encoded: bool,
     px: PhantomData<(Pixel, PixelStorage)>,
 }
 
+impl<ReadChannels, Pixel, PixelStorage, CreatePixels, SetPixel> CollectPixels<ReadChannels, Pixel, PixelStorage, CreatePixels, SetPixel> {
+
+    /// Apply the sRGB transfer function to the samples before they are passed to the pixel setter,
+    /// for display on a screen. Only channels that contain linear floating point values are encoded,
+    /// which excludes channels marked with `quantize_linearly`, such as alpha, and `u32` channels.
+    pub fn srgb_encoded(self) -> Self { Self { srgb_encoded: true, ..self } }
+}
+
 impl<Inner: CheckDuplicates, Sample> CheckDuplicates for ReadRequiredChannel<Inner, Sample> {
     fn already_contains(&self, name: &Text) -> bool {
         &self.channel_name == name || self.previous_channels.already_contains(name)
@@ -149,7 +180,9 @@ ReadChannels<'s> for CollectPixels<InnerChannels, Pixel, PixelStorage, CreatePix
     fn create_channels_reader(&'s self, header: &Header) -> Result<Self::Reader> {
         if header.deep { return Err(Error::invalid("`SpecificChannels` does not support deep data yet")) }
 
-        let pixel_reader = self.read_channels.create_recursive_reader(&header.channels)?;
+        let mut pixel_reader = self.read_channels.create_recursive_reader(&header.channels)?;
+        if self.srgb_encoded { pixel_reader.encode_srgb(); }
+
         let channel_descriptions = pixel_reader.get_descriptions().into_non_recursive();// TODO not call this twice
 
         let create = &self.create_pixels;
@@ -227,11 +260,10 @@ impl<DefaultSample, ReadChannels> ReadSpecificChannel for ReadOptionalChannel<Re
         debug_assert!(self.previous_channels.already_contains(&self.channel_name).not(), "duplicate channel name: {}", self.channel_name);
 
         let inner_samples_reader = self.previous_channels.create_recursive_reader(channels)?;
-        let reader = channels.channels_with_byte_offset()
-            .find(|(_, channel)| channel.name == self.channel_name)
+        let reader = find_channel(channels, &self.channel_name, self.match_suffix)
             .map(|(channel_byte_offset, channel)| SampleReader {
                 channel_byte_offset, channel: channel.clone(),
-                px: Default::default()
+                srgb_encoded: false, px: Default::default()
             });
 
         Ok(Recursive::new(inner_samples_reader, OptionalSampleReader {
@@ -247,14 +279,28 @@ impl<Sample, ReadChannels> ReadSpecificChannel for ReadRequiredChannel<ReadChann
 
     fn create_recursive_reader(&self, channels: &ChannelList) -> Result<Self::RecursivePixelReader> {
         let previous_samples_reader = self.previous_channels.create_recursive_reader(channels)?;
-        let (channel_byte_offset, channel) = channels.channels_with_byte_offset()
-                .find(|(_, channel)| channel.name == self.channel_name)
+        let (channel_byte_offset, channel) = find_channel(channels, &self.channel_name, self.match_suffix)
                 .ok_or_else(|| Error::invalid(format!(
                     "layer does not contain all of your specified channels (`{}` is missing)",
                     self.channel_name
                 )))?;
 
-        Ok(Recursive::new(previous_samples_reader, SampleReader { channel_byte_offset, channel: channel.clone(), px: Default::default() }))
+        Ok(Recursive::new(previous_samples_reader, SampleReader {
+            channel_byte_offset, channel: channel.clone(),
+            srgb_encoded: false, px: Default::default()
+        }))
+    }
+}
+
+/// Find the channel and its byte offset, either by its exact name or by its name after the layer prefix.
+fn find_channel<'c>(channels: &'c ChannelList, name: &Text, match_suffix: bool) -> Option<(usize, &'c ChannelDescription)> {
+    if match_suffix {
+        channels.find_index_of_channel_by_suffix(name)
+            .and_then(|index| channels.channels_with_byte_offset().nth(index))
+    }
+    else {
+        channels.channels_with_byte_offset()
+            .find(|(_, channel)| &channel.name == name)
     }
 }
 
@@ -266,6 +312,7 @@ pub struct SampleReader<Sample> {
     channel_byte_offset: usize,
 
     channel: ChannelDescription,
+    srgb_encoded: bool,
     px: PhantomData<Sample>
 }
 
@@ -278,6 +325,10 @@ pub struct OptionalSampleReader<DefaultSample> {
 }
 
 impl<Sample: FromNativeSample> SampleReader<Sample> {
+    fn encode_srgb(&mut self) {
+        self.srgb_encoded = !self.channel.quantize_linearly && self.channel.sample_type != SampleType::U32;
+    }
+
     fn read_own_samples<'s, FullPixel>(
         &self, bytes: &'s[u8], pixels: &mut [FullPixel],
         get_sample: impl Fn(&mut FullPixel) -> &mut Sample
@@ -287,22 +338,44 @@ impl<Sample: FromNativeSample> SampleReader<Sample> {
         let mut own_bytes_reader = &mut &bytes[start_index .. start_index + byte_count]; // TODO check block size somewhere
         let mut samples_out = pixels.iter_mut().map(|pixel| get_sample(pixel));
 
-        // match the type once for the whole line, not on every single sample
-        match self.channel.sample_type {
-            SampleType::F16 => read_and_convert_all_samples_batched(
-                &mut own_bytes_reader, &mut samples_out,
-                Sample::from_f16s
-            ),
-
-            SampleType::F32 => read_and_convert_all_samples_batched(
-                &mut own_bytes_reader, &mut samples_out,
-                Sample::from_f32s
-            ),
-
-            SampleType::U32 => read_and_convert_all_samples_batched(
-                &mut own_bytes_reader, &mut samples_out,
-                Sample::from_u32s
-            ),
+        if self.srgb_encoded {
+            // the transfer function is applied to f32 values, before converting to the desired type
+            let mut linear = vec![0.0_f32; samples_out.len()];
+
+            match self.channel.sample_type {
+                SampleType::F16 => read_and_convert_all_samples_batched(
+                    &mut own_bytes_reader, &mut linear.iter_mut(), f32::from_f16s
+                ),
+
+                SampleType::F32 => read_and_convert_all_samples_batched(
+                    &mut own_bytes_reader, &mut linear.iter_mut(), f32::from_f32s
+                ),
+
+                SampleType::U32 => unreachable!("integer samples are never encoded"),
+            }
+
+            for (sample, linear) in samples_out.by_ref().zip(linear) {
+                *sample = Sample::from_f32(linear_to_srgb(linear));
+            }
+        }
+        else {
+            // match the type once for the whole line, not on every single sample
+            match self.channel.sample_type {
+                SampleType::F16 => read_and_convert_all_samples_batched(
+                    &mut own_bytes_reader, &mut samples_out,
+                    Sample::from_f16s
+                ),
+
+                SampleType::F32 => read_and_convert_all_samples_batched(
+                    &mut own_bytes_reader, &mut samples_out,
+                    Sample::from_f32s
+                ),
+
+                SampleType::U32 => read_and_convert_all_samples_batched(
+                    &mut own_bytes_reader, &mut samples_out,
+                    Sample::from_u32s
+                ),
+            }
         }
 
         debug_assert!(samples_out.next().is_none(), "not all samples have been converted");
@@ -409,6 +482,8 @@ impl RecursivePixelReader for NoneMore {
         &self, _: &'s[u8], _: &mut [FullPixel],
         _: impl Fn(&mut FullPixel) -> &mut NoneMore
     ){}
+
+    fn encode_srgb(&mut self) {}
 }
 
 impl<Sample, InnerReader: RecursivePixelReader>
@@ -428,6 +503,11 @@ impl<Sample, InnerReader: RecursivePixelReader>
         self.value.read_own_samples(bytes, pixels, |px| &mut get_pixel(px).value);
         self.inner.read_pixels(bytes, pixels, |px| &mut get_pixel(px).inner);
     }
+
+    fn encode_srgb(&mut self) {
+        self.value.encode_srgb();
+        self.inner.encode_srgb();
+    }
 }
 
 impl<Sample, InnerReader: RecursivePixelReader>
@@ -458,6 +538,11 @@ for Recursive<InnerReader, OptionalSampleReader<Sample>>
 
         self.inner.read_pixels(bytes, pixels, |px| &mut get_pixel(px).inner);
     }
+
+    fn encode_srgb(&mut self) {
+        if let Some(reader) = &mut self.value.reader { reader.encode_srgb(); }
+        self.inner.encode_srgb();
+    }
 }
 
 
@@ -60,7 +60,7 @@ impl<'samples, Samples> WritableChannels<'samples> for AnyChannels<Samples>
     where Samples: 'samples + WritableSamples<'samples>
 {
     fn infer_channel_list(&self) -> ChannelList {
-        ChannelList::new(self.list.iter().map(|channel| ChannelDescription {
+        ChannelList::new(alphabetically_sorted(&self.list).into_iter().map(|channel| ChannelDescription {
             name: channel.name.clone(),
             sample_type: channel.sample_data.sample_type(),
             quantize_linearly: channel.quantize_linearly,
@@ -83,7 +83,7 @@ impl<'samples, Samples> WritableChannels<'samples> for AnyChannels<Samples>
 
     type Writer = AnyChannelsWriter<Samples::Writer>;
     fn create_writer(&'samples self, header: &Header) -> Self::Writer {
-        let channels = self.list.iter()
+        let channels = alphabetically_sorted(&self.list).into_iter()
             .map(|chan| chan.sample_data.create_samples_writer(header))
             .collect();
 
@@ -91,6 +91,15 @@ impl<'samples, Samples> WritableChannels<'samples> for AnyChannels<Samples>
     }
 }
 
+/// The file requires the channels to be sorted alphabetically,
+/// but the list of arbitrary channels may have been constructed in any order.
+/// Sorting is stable, such that the header and the writers always agree on the order.
+fn alphabetically_sorted<Samples>(list: &[AnyChannel<Samples>]) -> SmallVec<[&AnyChannel<Samples>; 4]> {
+    let mut sorted: SmallVec<[&AnyChannel<Samples>; 4]> = list.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    sorted
+}
+
 /// A temporary writer for an arbitrary list of channels
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct AnyChannelsWriter<SamplesWriter> {
@@ -18,9 +18,10 @@ pub mod channels;
 
 
 
-use crate::meta::Headers;
-use crate::error::UnitResult;
-use std::io::{Seek, BufWriter};
+use crate::meta::{Headers, MetaData, header};
+use crate::block::chunk::Chunk;
+use crate::error::{Error, Result, UnitResult};
+use std::io::{Seek, BufWriter, Cursor};
 use crate::io::Write;
 use crate::image::{Image, ignore_progress, SpecificChannels, IntoSample};
 use crate::image::write::layers::{WritableLayers, LayersWriter};
@@ -73,6 +74,8 @@ impl<'img, WritableLayers> WritableImage<'img, WritableLayers> for &'img Image<W
         WriteImageWithOptions {
             image: self,
             check_compatibility: true,
+            require_lossless: false,
+            strip_preview: false,
 
             #[cfg(not(feature = "rayon"))]
             parallel: false,
@@ -92,6 +95,8 @@ pub struct WriteImageWithOptions<'img, Layers, OnProgress> {
     image: &'img Image<Layers>,
     on_progress: OnProgress,
     check_compatibility: bool,
+    require_lossless: bool,
+    strip_preview: bool,
     parallel: bool,
 }
 
@@ -100,8 +105,29 @@ impl<'img, L, F> WriteImageWithOptions<'img, L, F>
     where L: WritableLayers<'img>, F: FnMut(f64)
 {
     /// Generate file meta data for this image. The meta data structure is close to the data in the file.
+    /// Does not contain any `preview` attribute if `strip_preview` was specified.
     pub fn infer_meta_data(&self) -> Headers { // TODO this should perform all validity checks? and none after that?
-        self.image.layer_data.infer_headers(&self.image.attributes)
+        let mut headers = self.image.layer_data.infer_headers(&self.image.attributes);
+
+        if self.strip_preview {
+            for header in &mut headers {
+                header.own_attributes.preview = None;
+                header.own_attributes.other.remove(&header::standard_names::PREVIEW[..]);
+            }
+        }
+
+        headers
+    }
+
+    /// Generate the file meta data, and check that it satisfies the options of this writer.
+    fn infer_checked_meta_data(&self) -> Result<Headers> {
+        let headers = self.infer_meta_data();
+
+        if self.require_lossless && headers.iter().any(|header| header.compression.may_loose_data()) {
+            return Err(Error::unsupported("lossy compression disallowed"));
+        }
+
+        Ok(headers)
     }
 
     /// Do not compress multiple pixel blocks on multiple threads at once.
@@ -118,6 +144,15 @@ impl<'img, L, F> WriteImageWithOptions<'img, L, F>
     /// __You must care for not producing an invalid file yourself.__
     pub fn skip_compatibility_checks(self) -> Self { Self { check_compatibility: false, ..self } }
 
+    /// Refuse to write the image if any layer uses a compression method that may lose data,
+    /// such as `PXR24`, `B44`, or `DWAA`. Writing will then fail with an `Error::NotSupported`
+    /// before any pixels are compressed. Useful for archival, where no data must ever be lost.
+    pub fn require_lossless(self) -> Self { Self { require_lossless: true, ..self } }
+
+    /// Do not write the `preview` attribute of any layer, even if the image contains one.
+    /// Saves space in files where no thumbnail is needed. The image itself is not modified.
+    pub fn strip_preview(self) -> Self { Self { strip_preview: true, ..self } }
+
     /// Specify a function to be called regularly throughout the writing process.
     /// Replaces all previously specified progress functions in this reader.
     pub fn on_progress<OnProgress>(self, on_progress: OnProgress) -> WriteImageWithOptions<'img, L, OnProgress>
@@ -127,6 +162,8 @@ impl<'img, L, F> WriteImageWithOptions<'img, L, F>
             on_progress,
             image: self.image,
             check_compatibility: self.check_compatibility,
+            require_lossless: self.require_lossless,
+            strip_preview: self.strip_preview,
             parallel: self.parallel
         }
     }
@@ -146,19 +183,30 @@ impl<'img, L, F> WriteImageWithOptions<'img, L, F>
     /// Use `to_buffered` instead, if your writer is an in-memory buffer.
     /// Use `to_file` instead, if you have a file path.
     /// If your writer cannot seek, you can write to an in-memory vector of bytes first, using `to_buffered`.
+    /// The buffer is flushed before this function returns, so that any delayed io errors are reported.
     #[inline]
     #[must_use]
     pub fn to_unbuffered(self, unbuffered: impl Write + Seek) -> UnitResult {
         self.to_buffered(BufWriter::new(unbuffered))
     }
 
+    /// Write the exr image to a new in-memory buffer, without creating a temporary file.
+    /// Use `to_buffered` instead, if you want to reuse an existing vector of bytes.
+    #[must_use]
+    pub fn to_vec(self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.to_buffered(Cursor::new(&mut bytes))?;
+        Ok(bytes)
+    }
+
     /// Write the exr image to a writer.
     /// Use `to_file` instead, if you have a file path.
     /// Use `to_unbuffered` instead, if this is not an in-memory writer.
     /// If your writer cannot seek, you can write to an in-memory vector of bytes first.
     #[must_use]
     pub fn to_buffered(self, write: impl Write + Seek) -> UnitResult {
-        let headers = self.infer_meta_data();
+        let headers = self.infer_checked_meta_data()?;
+
         let layers = self.image.layer_data.create_writer(&headers);
 
         crate::block::write(
@@ -192,5 +240,58 @@ impl<'img, L, F> WriteImageWithOptions<'img, L, F>
             }
         )
     }
+
+    /// Compress all pixels of the image, without writing a file.
+    /// Returns the headers and all compressed chunks, in the order they would appear in the file.
+    /// Each chunk is paired with its index in the header, as if the chunks were sorted by increasing y.
+    /// The result can be written using the low-level `exr::block::write` function,
+    /// producing the same file as `to_buffered`.
+    /// Unlike `to_buffered`, all compressed chunks are held in memory at the same time.
+    #[must_use]
+    pub fn to_chunks(mut self) -> Result<(Headers, Vec<(usize, Chunk)>)> {
+        let headers = self.infer_checked_meta_data()?;
+
+        let requirements = MetaData::validate(headers.as_slice(), self.check_compatibility)?;
+        let meta = MetaData { requirements, headers };
+
+        let layers = self.image.layer_data.create_writer(&meta.headers);
+        let blocks = meta.collect_ordered_block_data(|block_index|
+            layers.extract_uncompressed_block(&meta.headers, block_index)
+        );
+
+        let total_chunks_count = meta.headers.iter().map(|header| header.chunk_count).sum();
+        let mut collector = ChunksCollector { chunks: Vec::with_capacity(total_chunks_count), total_chunks_count };
+
+        {
+            let chunk_writer = collector.on_progress(&mut self.on_progress);
+
+            if self.parallel {
+                #[cfg(not(feature = "rayon"))]
+                return Err(crate::error::Error::unsupported("parallel compression requires the rayon feature"));
+
+                #[cfg(feature = "rayon")]
+                chunk_writer.compress_all_blocks_parallel(&meta, blocks)?;
+            }
+            else { chunk_writer.compress_all_blocks_sequential(&meta, blocks)?; }
+        }
+
+        Ok((meta.headers, collector.chunks))
+    }
+}
+
+/// Keeps the compressed chunks in memory, instead of writing them to a file.
+#[derive(Debug)]
+struct ChunksCollector {
+    chunks: Vec<(usize, Chunk)>,
+    total_chunks_count: usize,
+}
+
+impl ChunksWriter for ChunksCollector {
+    fn total_chunks_count(&self) -> usize { self.total_chunks_count }
+
+    fn write_chunk(&mut self, index_in_header_increasing_y: usize, chunk: Chunk) -> UnitResult {
+        self.chunks.push((index_in_header_increasing_y, chunk));
+        Ok(())
+    }
 }
 
@@ -14,6 +14,13 @@ use std::path::Path;
 use std::fs::File;
 use std::convert::TryFrom;
 
+#[cfg(feature = "async")]
+use {
+    std::collections::BTreeMap,
+    std::ops::Range,
+    tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt},
+};
+
 
 /// Skip reading uninteresting bytes without allocating.
 #[inline]
@@ -258,6 +265,105 @@ impl<T: Write + Seek> Tracking<T> {
 }
 
 
+/// A file of which only some byte ranges have been loaded into memory.
+/// Reading bytes that have not been loaded behaves as if the file ended there,
+/// which results in the usual missing bytes error while parsing.
+/// Allows parsing bytes from an asynchronous source with the synchronous parser,
+/// after loading the required byte ranges asynchronously.
+#[cfg(feature = "async")]
+#[derive(Debug, Default)]
+pub struct PartiallyLoadedBytes {
+
+    /// Loaded byte ranges, by their start position in the file. The ranges do not overlap.
+    ranges: BTreeMap<u64, Vec<u8>>,
+
+    position: u64,
+    file_size: u64,
+}
+
+#[cfg(feature = "async")]
+impl PartiallyLoadedBytes {
+
+    /// A file of the specified size, without any bytes loaded yet.
+    pub fn new(file_size: u64) -> Self {
+        Self { ranges: BTreeMap::new(), position: 0, file_size }
+    }
+
+    /// The current reading position in the file.
+    pub fn position(&self) -> u64 { self.position }
+
+    /// Load the specified byte range from the asynchronous source.
+    /// Skips the bytes that have already been loaded.
+    pub async fn load(&mut self, source: &mut (impl AsyncRead + AsyncSeek + Unpin), range: Range<u64>) -> IoResult<()> {
+        let mut start = range.start;
+
+        while start < range.end {
+            let loaded_before = self.ranges.range(..= start).next_back()
+                .map(|(&loaded_start, bytes)| (loaded_start, loaded_start + bytes.len() as u64));
+
+            if let Some((_, loaded_end)) = loaded_before {
+                if loaded_end > start {
+                    start = loaded_end;
+                    continue;
+                }
+            }
+
+            let end = self.ranges.range(start ..).next()
+                .map_or(range.end, |(&loaded_start, _)| loaded_start.min(range.end));
+
+            source.seek(SeekFrom::Start(start)).await?;
+            let mut bytes = vec![0_u8; usize::try_from(end - start).expect("byte range too large")];
+            source.read_exact(&mut bytes).await?;
+
+            // extend the previous range if possible, so that it can be read without interruption
+            match loaded_before {
+                Some((loaded_start, loaded_end)) if loaded_end == start =>
+                    self.ranges.get_mut(&loaded_start).expect("loaded range missing").extend_from_slice(&bytes),
+
+                _ => { self.ranges.insert(start, bytes); },
+            }
+
+            start = end;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl Read for PartiallyLoadedBytes {
+    fn read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
+        let position = self.position;
+
+        let loaded = self.ranges.range(..= position).next_back()
+            .and_then(|(&start, bytes)| bytes.get(usize::try_from(position - start).ok()? ..))
+            .unwrap_or(&[]);
+
+        let count = loaded.len().min(buffer.len());
+        buffer[.. count].copy_from_slice(&loaded[.. count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Seek for PartiallyLoadedBytes {
+    fn seek(&mut self, target: SeekFrom) -> IoResult<u64> {
+        let position = match target {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::End(offset) => u64::try_from(i128::from(self.file_size) + i128::from(offset)).ok(),
+            SeekFrom::Current(offset) => u64::try_from(i128::from(self.position) + i128::from(offset)).ok(),
+        };
+
+        self.position = position.ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput, "seek to a negative position"
+        ))?;
+
+        Ok(self.position)
+    }
+}
+
+
 /// Generic trait that defines common binary operations such as reading and writing for this type.
 pub trait Data: Sized + Default + Clone {
 
@@ -283,10 +389,10 @@ pub trait Data: Sized + Default + Clone {
         Ok(vec)
     }
 
-    /// Write this value to the writer.
+    /// Write this value to the writer, in little-endian byte order, regardless of the host.
     fn write(self, write: &mut impl Write) -> UnitResult;
 
-    /// Write all values of that slice to the writer.
+    /// Write all values of that slice to the writer, in little-endian byte order, regardless of the host.
     fn write_slice(write: &mut impl Write, slice: &[Self]) -> UnitResult;
 
 
@@ -416,8 +522,9 @@ impl Data for f16 {
 
 #[cfg(test)]
 mod test {
-    use crate::io::PeekRead;
+    use crate::io::{PeekRead, Data};
     use std::io::Read;
+    use half::f16;
 
     #[test]
     fn peek(){
@@ -442,6 +549,24 @@ mod test {
 
         assert!(u8::read_from_little_endian(&mut peek).is_err());
     }
-}
-
 
+    #[test]
+    fn write_slices_as_little_endian(){
+        let floats = [ 1.0_f32, -2.5, f32::MAX, 0.1 ];
+        let mut bytes = Vec::new();
+        f32::write_slice(&mut bytes, &floats).unwrap();
+
+        let expected: Vec<u8> = floats.iter().flat_map(|value| value.to_le_bytes()).collect();
+        assert_eq!(bytes, expected);
+        assert_eq!(&bytes[.. 4], &[0x00, 0x00, 0x80, 0x3f], "1.0 must be written as little endian");
+
+        let halfs = [ f16::from_f32(1.0), f16::from_f32(-2.0) ];
+        let mut bytes = Vec::new();
+        f16::write_slice(&mut bytes, &halfs).unwrap();
+        assert_eq!(bytes, vec![ 0x00, 0x3c, 0x00, 0xc0 ]);
+
+        let mut decoded = [ f32::default(); 4 ];
+        f32::read_slice(&mut expected.as_slice(), &mut decoded).unwrap();
+        assert_eq!(decoded, floats);
+    }
+}
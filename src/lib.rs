@@ -70,10 +70,13 @@ pub mod prelude {
     pub use crate::image::write::{write_rgb_file, write_rgba_file};
     pub use crate::image::read::{
         read_first_rgba_layer_from_file,
+        read_first_rgba_layer_as_f16,
         read_all_rgba_layers_from_file,
         read_all_data_from_file,
         read_all_flat_layers_from_file,
-        read_first_flat_layer_from_file
+        read_first_flat_layer_from_file,
+        read_preview_from_file,
+        read_channels_from_file
     };
 
     // image data structures
@@ -7,7 +7,7 @@
 use std::convert::TryFrom;
 use crate::error::{i32_to_usize};
 use crate::error::Result;
-use std::ops::{Add, Sub, Div, Mul};
+use std::ops::{Add, Div, Mul, Rem};
 use std::fmt::Debug;
 
 /// Simple two-dimensional vector of any numerical type.
@@ -196,7 +196,7 @@ impl RoundingMode {
 
     /// Only works for positive numbers.
     pub(crate) fn divide<T>(self, dividend: T, divisor: T) -> T
-        where T: Copy + Add<Output = T> + Sub<Output = T> + Div<Output = T> + From<u8> + std::cmp::PartialOrd
+        where T: Copy + Add<Output = T> + Div<Output = T> + Rem<Output = T> + From<u8> + std::cmp::PartialOrd
     {
         assert!(
             dividend >= T::from(0) && divisor >= T::from(1),
@@ -204,7 +204,13 @@ impl RoundingMode {
         );
 
         match self {
-            RoundingMode::Up => (dividend + divisor - T::from(1_u8)) / divisor, // only works for positive numbers
+            // does not compute `dividend + divisor - 1`, which would overflow for large dividends.
+            // adding one to the quotient cannot overflow, as a remainder implies a divisor of at least two
+            RoundingMode::Up => {
+                let quotient = dividend / divisor;
+                if dividend % divisor > T::from(0) { quotient + T::from(1) } else { quotient }
+            },
+
             RoundingMode::Down => dividend / divisor,
         }
     }
@@ -82,6 +82,12 @@ pub enum AttributeValue {
     /// 3D float vector.
     FloatVec3((f32, f32, f32)),
 
+    /// 2D 64-bit float vector.
+    F64Vec2(Vec2<f64>),
+
+    /// 3D 64-bit float vector.
+    F64Vec3((f64, f64, f64)),
+
     /// A custom attribute.
     /// Contains the type name of this value.
     Custom {
@@ -187,6 +193,18 @@ pub type DisplayWindow = IntegerBounds;
 /// An integer dividend and divisor, together forming a ratio.
 pub type Rational = (i32, u32);
 
+/// The dividend and divisor of a `Rational` attribute value.
+/// Offers conversions that are useful for displaying ratios such as frame rates.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct RationalParts {
+
+    /// The dividend of the ratio. May be negative.
+    pub numerator: i32,
+
+    /// The divisor of the ratio. May be zero in invalid files.
+    pub denominator: u32,
+}
+
 /// A float matrix with four rows and four columns.
 pub type Matrix4x4 = [f32; 4*4];
 
@@ -296,6 +314,29 @@ pub struct Chromaticities {
     pub white: Vec2<f32>
 }
 
+/// The components of a `capDate` attribute, which is stored as text in `YYYY:MM:DD hh:mm:ss` format.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct CaptureDate {
+
+    /// The year, for example `2007`.
+    pub year: u16,
+
+    /// The month, from `1` to `12`.
+    pub month: u8,
+
+    /// The day of the month, from `1` to `31`.
+    pub day: u8,
+
+    /// The hour, from `0` to `23`.
+    pub hour: u8,
+
+    /// The minute, from `0` to `59`.
+    pub minute: u8,
+
+    /// The second, from `0` to `60`, allowing for leap seconds.
+    pub second: u8,
+}
+
 /// If this attribute is present, it describes
 /// how this texture should be projected onto an environment.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
@@ -308,6 +349,24 @@ pub enum EnvironmentMap {
     Cube,
 }
 
+/// How a texture is extrapolated outside of its data window.
+/// Stored in the `wrapmodes` text attribute, for example as `clamp,periodic`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum WrapMode {
+
+    /// Repeat the pixels at the edge of the texture.
+    Clamp,
+
+    /// All pixels outside the texture are black.
+    Black,
+
+    /// Repeat the whole texture.
+    Periodic,
+
+    /// Repeat the whole texture, mirroring every other repetition.
+    Mirror,
+}
+
 /// Uniquely identifies a motion picture film frame.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub struct KeyCode {
@@ -409,12 +468,13 @@ pub type TextSlice = [u8];
 
 
 use crate::io::*;
-use crate::meta::{sequence_end};
+use crate::meta::{sequence_end, compute_block_count};
 use crate::error::*;
 use crate::math::{RoundingMode, Vec2};
 use half::f16;
 use std::convert::{TryFrom};
 use std::borrow::Borrow;
+use std::io::{Seek, SeekFrom};
 use std::hash::{Hash, Hasher};
 use bit_field::BitField;
 
@@ -427,6 +487,8 @@ fn invalid_type() -> Error {
 impl Text {
 
     /// Create a `Text` from an `str` reference.
+    /// Each char is stored as a single Latin-1 byte,
+    /// so only the chars `U+0000` to `U+00FF` are supported.
     /// Returns `None` if this string contains unsupported chars.
     pub fn new_or_none(string: impl AsRef<str>) -> Option<Self> {
         let vec : Option<TextBytes> = string.as_ref().chars()
@@ -437,7 +499,9 @@ impl Text {
     }
 
     /// Create a `Text` from an `str` reference.
-    /// Panics if this string contains unsupported chars.
+    /// Each char is stored as a single Latin-1 byte.
+    /// Panics if this string contains unsupported chars, also in release builds.
+    /// Use `str::parse` or `Text::new_or_none` to handle unsupported chars instead.
     pub fn new_or_panic(string: impl AsRef<str>) -> Self {
         Self::new_or_none(string).expect("exr::Text contains unsupported characters")
     }
@@ -506,7 +570,8 @@ impl Text {
     /// Read the length of a string and then the contents with that length.
     pub fn read_i32_sized<R: Read>(read: &mut R, max_size: usize) -> Result<Self> {
         let size = i32_to_usize(i32::read(read)?, "vector size")?;
-        Ok(Text::from_bytes_unchecked(SmallVec::from_vec(u8::read_vec(read, size, 1024, Some(max_size), "text attribute length")?)))
+        if size > max_size { return Err(Error::invalid("text attribute length")) }
+        Self::read_sized(read, size)
     }
 
     /// Read the contents with that length.
@@ -543,11 +608,20 @@ impl Text {
     }
 
     /// Read a string until the null-terminator is found. Then skips the null-terminator.
+    /// The text is never longer than 255 bytes, which is the maximum length of long names.
     pub fn read_null_terminated<R: Read>(read: &mut R, max_len: usize) -> Result<Self> {
-        let mut bytes = smallvec![ u8::read(read)? ]; // null-terminated strings are always at least 1 byte
+        let max_len = max_len.min(255);
+
+        // a file that ends before the terminator is not simply missing some bytes
+        let read_byte = |read: &mut R| u8::read(read).map_err(|error| match error {
+            Error::Invalid(message) if message == MISSING_BYTES => Error::invalid("unterminated text"),
+            error => error,
+        });
+
+        let mut bytes = smallvec![ read_byte(read)? ]; // null-terminated strings are always at least 1 byte
 
         loop {
-            match u8::read(read)? {
+            match read_byte(read)? {
                 0 => break,
                 non_terminator => bytes.push(non_terminator),
             }
@@ -573,7 +647,9 @@ impl Text {
         let mut processed_bytes = 0;
 
         while processed_bytes < total_byte_size {
-            let text = Text::read_i32_sized(read, total_byte_size)?;
+            // a single text can never be larger than the remaining bytes of the attribute
+            let max_text_size = (total_byte_size - processed_bytes).saturating_sub(i32::BYTE_SIZE);
+            let text = Text::read_i32_sized(read, max_text_size)?;
             processed_bytes += ::std::mem::size_of::<i32>(); // size i32 of the text
             processed_bytes += text.bytes.len();
             result.push(text);
@@ -665,6 +741,15 @@ impl<'s> From<&'s str> for Text {
     }
 }
 
+impl std::str::FromStr for Text {
+    type Err = Error;
+
+    /// Returns an error if the string contains a char that is not Latin-1.
+    fn from_str(str: &str) -> Result<Self> {
+        Self::new_or_none(str).ok_or(Error::invalid("text contains unsupported characters"))
+    }
+}
+
 
 /* TODO (currently conflicts with From<&str>)
 impl<'s> TryFrom<&'s str> for Text {
@@ -700,6 +785,15 @@ impl ::std::fmt::Display for Text {
 }
 
 
+/// Split a channel name into the layer prefix and the channel name, at the last `.`.
+/// Names without a prefix result in an empty layer name.
+fn split_layer_prefix(name: &[u8]) -> (&[u8], &[u8]) {
+    match name.iter().rposition(|&byte| byte == b'.') {
+        Some(dot) => (&name[.. dot], &name[dot + 1 ..]),
+        None => (&name[.. 0], name),
+    }
+}
+
 impl ChannelList {
 
     /// Does not validate channel order.
@@ -736,6 +830,42 @@ impl ChannelList {
         self.list.binary_search_by_key(&exact_name.bytes(), |chan| chan.name.bytes()).ok()
     }
 
+    /// Group the channels by the layer name prefix, which is separated from the channel name by the last `.`.
+    /// For example, `diffuse.R` and `diffuse.G` are grouped into the layer `diffuse`.
+    /// Channels without a prefix, such as `Z`, are grouped into the layer with the empty name.
+    /// The channels in each group retain their order from this list.
+    pub fn grouped_by_layer(&self) -> std::collections::BTreeMap<Text, Vec<&ChannelDescription>> {
+        let mut groups = std::collections::BTreeMap::<Text, Vec<&ChannelDescription>>::new();
+
+        for channel in &self.list {
+            let (layer_name, _) = split_layer_prefix(channel.name.bytes());
+            groups.entry(Text::from_slice_unchecked(layer_name)).or_default().push(channel);
+        }
+
+        groups
+    }
+
+    /// Return the index of the channel with the exact name, if there is one.
+    /// Otherwise, return the index of the only channel whose name, after the last `.`,
+    /// equals the specified name, ignoring case. This allows finding the channel `R`
+    /// in a layer that stores it as `diffuse.R` or `r`.
+    /// Returns none if no channel or more than one channel matches.
+    pub fn find_index_of_channel_by_suffix(&self, name: &Text) -> Option<usize> {
+        if let Some(exact) = self.find_index_of_channel(name) {
+            return Some(exact);
+        }
+
+        let mut matches = self.list.iter().enumerate().filter(|(_, channel)| {
+            let (_, channel_name) = split_layer_prefix(channel.name.bytes());
+            channel_name.eq_ignore_ascii_case(name.bytes())
+        });
+
+        match (matches.next(), matches.next()) {
+            (Some((index, _)), None) => Some(index),
+            _ => None,
+        }
+    }
+
     // TODO use this in compression methods
     /*pub fn pixel_section_indices(&self, bounds: IntegerBounds) -> impl '_ + Iterator<Item=(&Channel, usize, usize)> {
         (bounds.position.y() .. bounds.end().y()).flat_map(|y| {
@@ -755,19 +885,45 @@ impl BlockType {
     /// The corresponding attribute type name literal
     const TYPE_NAME: &'static [u8] = type_names::TEXT;
 
+    /// The block type of a part with the specified deepness and block layout.
+    pub fn new(deep: bool, tiled: bool) -> Self {
+        match (deep, tiled) {
+            (false, false) => BlockType::ScanLine,
+            (false, true) => BlockType::Tile,
+            (true, false) => BlockType::DeepScanLine,
+            (true, true) => BlockType::DeepTile,
+        }
+    }
+
     /// Return a `BlockType` object from the specified attribute text value.
     pub fn parse(text: Text) -> Result<Self> {
-        match text.as_slice() {
-            block_type_strings::SCAN_LINE => Ok(BlockType::ScanLine),
-            block_type_strings::TILE => Ok(BlockType::Tile),
+        Self::from_text_bytes(text.as_slice())
+            .ok_or(Error::invalid("block type attribute value"))
+    }
 
-            block_type_strings::DEEP_SCAN_LINE => Ok(BlockType::DeepScanLine),
-            block_type_strings::DEEP_TILE => Ok(BlockType::DeepTile),
+    /// Return the block type the raw attribute text value represents, or none if the value is unknown.
+    pub fn from_text_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            block_type_strings::SCAN_LINE => Some(BlockType::ScanLine),
+            block_type_strings::TILE => Some(BlockType::Tile),
 
-            _ => Err(Error::invalid("block type attribute value")),
+            block_type_strings::DEEP_SCAN_LINE => Some(BlockType::DeepScanLine),
+            block_type_strings::DEEP_TILE => Some(BlockType::DeepTile),
+
+            _ => None,
         }
     }
 
+    /// Whether blocks of this type contain deep data.
+    pub fn is_deep(self) -> bool {
+        self == BlockType::DeepScanLine || self == BlockType::DeepTile
+    }
+
+    /// Whether blocks of this type are tiles instead of scan lines.
+    pub fn is_tiled(self) -> bool {
+        self == BlockType::Tile || self == BlockType::DeepTile
+    }
+
     /// Without validation, write this instance to the byte stream.
     pub fn write(&self, write: &mut impl Write) -> UnitResult {
         u8::write_slice(write, self.to_text_bytes())?;
@@ -808,6 +964,12 @@ impl IntegerBounds {
         Self { position: start.into(), size: size.into() }
     }
 
+    /// Returns the minimum coordinate of the rectangle, which may be negative.
+    /// The `size` of the rectangle is counted from this coordinate.
+    pub fn origin(self) -> Vec2<i32> {
+        self.position
+    }
+
     /// Returns the top-right coordinate of the rectangle.
     /// The row and column described by this vector are not included in the rectangle,
     /// just like `Vec::len()`.
@@ -876,17 +1038,18 @@ impl IntegerBounds {
         let x_max = i32::read(read)?;
         let y_max = i32::read(read)?;
 
-        let min = Vec2(x_min.min(x_max), y_min.min(y_max));
-        let max  = Vec2(x_min.max(x_max), y_min.max(y_max));
+        let min = Vec2(x_min, y_min);
+        let max  = Vec2(x_max, y_max);
 
         // prevent addition overflow
         Self::validate_min_max_u64(
-            Vec2(min.x() as i64, min.y() as i64),
-            Vec2(max.x() as i64, max.y() as i64),
+            Vec2(min.x().min(max.x()) as i64, min.y().min(max.y()) as i64),
+            Vec2(min.x().max(max.x()) as i64, min.y().max(max.y()) as i64),
         )?;
 
-        // add one to max because the max inclusive, but the size is not
-        let size = Vec2(max.x() + 1 - min.x(), max.y() + 1 - min.y());
+        // add one to max because the max inclusive, but the size is not.
+        // a maximum smaller than the minimum denotes an empty box
+        let size = Vec2((max.x() + 1 - min.x()).max(0), (max.y() + 1 - min.y()).max(0));
         let size = size.to_usize("box coordinates")?;
 
         Ok(IntegerBounds { position: min, size })
@@ -935,6 +1098,16 @@ impl FloatRect {
             max: Vec2(x_max, y_max)
         })
     }
+
+    /// Validate this instance.
+    /// In strict mode, the minimum must not be larger than the maximum.
+    pub fn validate(&self, strict: bool) -> UnitResult {
+        if strict && (self.min.x() > self.max.x() || self.min.y() > self.max.y()) {
+            return Err(Error::invalid("rectangle minimum larger than maximum"));
+        }
+
+        Ok(())
+    }
 }
 
 impl SampleType {
@@ -1098,6 +1271,11 @@ impl ChannelDescription {
 
 impl ChannelList {
 
+    /// The maximum number of channels a valid channel list may contain.
+    /// Files with more channels are considered corrupt,
+    /// as the pixel data of such a list would be unreasonably large.
+    pub const MAX_CHANNEL_COUNT: usize = u16::MAX as usize;
+
     /// Number of bytes this would consume in an exr file.
     pub fn byte_size(&self) -> usize {
         self.list.iter().map(ChannelDescription::byte_size).sum::<usize>() + sequence_end::byte_size()
@@ -1126,6 +1304,10 @@ impl ChannelList {
 
     /// Check if channels are valid and sorted.
     pub fn validate(&self, allow_sampling: bool, data_window: IntegerBounds, strict: bool) -> UnitResult {
+        if self.list.len() > Self::MAX_CHANNEL_COUNT {
+            return Err(Error::invalid("too many channels"));
+        }
+
         let mut iter = self.list.iter().map(|chan| chan.validate(allow_sampling, data_window, strict).map(|_| &chan.name));
         let mut previous = iter.next().ok_or(Error::invalid("at least one channel is required"))??;
 
@@ -1307,8 +1489,98 @@ impl TimeCode {
     }
 }
 
+impl CaptureDate {
+
+    /// Parse text in `YYYY:MM:DD hh:mm:ss` format, as stored in the `capDate` attribute.
+    /// Returns `None` if the text does not have exactly this format, or a component is out of range.
+    pub fn parse(text: &Text) -> Option<Self> {
+        let bytes = text.as_slice();
+        if bytes.len() != 19 || &bytes[4..5] != b":" || &bytes[7..8] != b":" || &bytes[10..11] != b" "
+            || &bytes[13..14] != b":" || &bytes[16..17] != b":" { return None; }
+
+        let number = |start: usize, end: usize| -> Option<u16> {
+            bytes[start .. end].iter().try_fold(0_u16, |number, &digit| {
+                if digit.is_ascii_digit() { Some(number * 10 + (digit - b'0') as u16) } else { None }
+            })
+        };
+
+        let date = CaptureDate {
+            year: number(0, 4)?,
+            month: number(5, 7)? as u8,
+            day: number(8, 10)? as u8,
+            hour: number(11, 13)? as u8,
+            minute: number(14, 16)? as u8,
+            second: number(17, 19)? as u8,
+        };
+
+        let in_range = (1 ..= 12).contains(&date.month) && (1 ..= 31).contains(&date.day)
+            && date.hour < 24 && date.minute < 60 && date.second <= 60;
+
+        if in_range { Some(date) } else { None }
+    }
+
+    /// Format this date as `YYYY:MM:DD hh:mm:ss`, as stored in the `capDate` attribute.
+    pub fn to_text(&self) -> Text {
+        Text::new_or_panic(format!(
+            "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        ))
+    }
+}
+
+impl WrapMode {
+
+    /// Parse a single wrap mode, such as `clamp`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "clamp" => Some(WrapMode::Clamp),
+            "black" => Some(WrapMode::Black),
+            "periodic" => Some(WrapMode::Periodic),
+            "mirror" => Some(WrapMode::Mirror),
+            _ => None,
+        }
+    }
+
+    /// The name of this wrap mode, as stored in the `wrapmodes` attribute.
+    pub fn name(self) -> &'static str {
+        match self {
+            WrapMode::Clamp => "clamp",
+            WrapMode::Black => "black",
+            WrapMode::Periodic => "periodic",
+            WrapMode::Mirror => "mirror",
+        }
+    }
+
+    /// Parse the horizontal and vertical wrap mode from text in `horizontal,vertical` format,
+    /// as stored in the `wrapmodes` attribute. A single wrap mode applies to both directions.
+    /// Returns `None` if the text does not have this format, or contains an unknown wrap mode.
+    pub fn parse_pair(text: &Text) -> Option<(Self, Self)> {
+        let text = std::str::from_utf8(text.as_slice()).ok()?;
+        let mut modes = text.split(',').map(|name| Self::parse(name.trim()));
+
+        let horizontal = modes.next()??;
+        let vertical = modes.next().unwrap_or(Some(horizontal))?;
+
+        if modes.next().is_some() { None }
+        else { Some((horizontal, vertical)) }
+    }
+
+    /// Format the horizontal and vertical wrap mode as `horizontal,vertical`, as stored in the `wrapmodes` attribute.
+    pub fn pair_to_text(horizontal: Self, vertical: Self) -> Text {
+        Text::new_or_panic(format!("{},{}", horizontal.name(), vertical.name()))
+    }
+}
+
 impl Chromaticities {
 
+    /// The primaries and white point of the ACES 2065-1 color space, also known as AP0.
+    pub const ACES_AP0: Chromaticities = Chromaticities {
+        red: Vec2(0.7347, 0.2653),
+        green: Vec2(0.0, 1.0),
+        blue: Vec2(0.0001, -0.0770),
+        white: Vec2(0.32168, 0.33767),
+    };
+
     /// Number of bytes this would consume in an exr file.
     pub fn byte_size() -> usize {
         8 * f32::BYTE_SIZE
@@ -1339,6 +1611,27 @@ impl Chromaticities {
             white: Vec2(f32::read(read)?, f32::read(read)?),
         })
     }
+
+    /// Check that all coordinates are finite, that the primaries are distinct,
+    /// and that the white point has positive coordinates.
+    /// Primaries may lie outside of the visible gamut, as in `ACES_AP0`.
+    /// Not checked when reading or writing a file.
+    pub fn validate(&self) -> UnitResult {
+        let points = [ self.red, self.green, self.blue, self.white ];
+        if points.iter().any(|point| !point.x().is_finite() || !point.y().is_finite()) {
+            return Err(Error::invalid("chromaticity coordinates"));
+        }
+
+        if self.red == self.green || self.green == self.blue || self.blue == self.red {
+            return Err(Error::invalid("chromaticity primaries are not distinct"));
+        }
+
+        if self.white.x() <= 0.0 || self.white.y() <= 0.0 {
+            return Err(Error::invalid("chromaticity white point"));
+        }
+
+        Ok(())
+    }
 }
 
 impl Compression {
@@ -1385,6 +1678,36 @@ impl Compression {
 
 impl EnvironmentMap {
 
+    /// Number of faces in a cube environment map.
+    /// The faces are stacked vertically in the order +X, -X, +Y, -Y, +Z, -Z.
+    pub const CUBE_FACE_COUNT: usize = 6;
+
+    /// The sub-window of the specified face inside the data window of a cube environment map.
+    /// Each face is a square with the width of the data window,
+    /// and the faces are stacked vertically in the order +X, -X, +Y, -Y, +Z, -Z.
+    /// Returns an error if the face index is not smaller than `CUBE_FACE_COUNT`,
+    /// or if the data window is not exactly six faces high.
+    pub fn cube_face_window(face: usize, data_window: IntegerBounds) -> Result<IntegerBounds> {
+        if face >= Self::CUBE_FACE_COUNT {
+            return Err(Error::invalid("cube face index"));
+        }
+
+        let face_size = data_window.size.width();
+        if face_size.checked_mul(Self::CUBE_FACE_COUNT) != Some(data_window.size.height()) {
+            return Err(Error::invalid("cube map data window must be six times as high as wide"));
+        }
+
+        let face_y = face.checked_mul(face_size)
+            .and_then(|offset| i32::try_from(offset).ok())
+            .and_then(|offset| data_window.position.y().checked_add(offset))
+            .ok_or(Error::invalid("cube map data window"))?;
+
+        Ok(IntegerBounds::new(
+            Vec2(data_window.position.x(), face_y),
+            Vec2(face_size, face_size)
+        ))
+    }
+
     /// Number of bytes this would consume in an exr file.
     pub fn byte_size() -> usize {
         u8::BYTE_SIZE
@@ -1444,6 +1767,58 @@ impl KeyCode {
     }
 }
 
+impl RationalParts {
+
+    /// Split a rational value into its dividend and divisor.
+    pub fn new(rational: Rational) -> Self {
+        let (numerator, denominator) = rational;
+        Self { numerator, denominator }
+    }
+
+    /// Convert back to the tuple representation used in attributes.
+    pub fn to_rational(self) -> Rational {
+        (self.numerator, self.denominator)
+    }
+
+    /// The value of the ratio as a float, for example `29.97` for `30000/1001`.
+    /// Returns `None` if the denominator is zero.
+    pub fn to_f64(self) -> Option<f64> {
+        if self.denominator == 0 { None }
+        else { Some(self.numerator as f64 / self.denominator as f64) }
+    }
+
+    /// Divide numerator and denominator by their greatest common divisor.
+    /// A zero denominator is returned unchanged, as it cannot be reduced.
+    pub fn reduced(self) -> Self {
+        fn greatest_common_divisor(mut a: u64, mut b: u64) -> u64 {
+            while b != 0 {
+                let remainder = a % b;
+                a = b;
+                b = remainder;
+            }
+
+            a
+        }
+
+        if self.denominator == 0 { return self; }
+
+        let divisor = greatest_common_divisor(
+            i64::from(self.numerator).unsigned_abs(),
+            u64::from(self.denominator)
+        );
+
+        Self {
+            // the divisor divides both values exactly, so the results fit into the original types
+            numerator: (i64::from(self.numerator) / divisor as i64) as i32,
+            denominator: (u64::from(self.denominator) / divisor) as u32,
+        }
+    }
+}
+
+impl From<Rational> for RationalParts {
+    fn from(rational: Rational) -> Self { Self::new(rational) }
+}
+
 impl LineOrder {
 
     /// Number of bytes this would consume in an exr file.
@@ -1480,6 +1855,37 @@ impl LineOrder {
 
 impl Preview {
 
+    /// Create a preview from unsigned bytes, four per pixel, as red, green, blue, alpha.
+    /// The bytes are reinterpreted, such that `255` is stored as `-1` in the `pixel_data`.
+    pub fn from_u8_rgba(size: impl Into<Vec2<usize>>, rgba: Vec<u8>) -> Self {
+        Preview {
+            size: size.into(),
+            pixel_data: rgba.into_iter().map(|byte| byte as i8).collect(),
+        }
+    }
+
+    /// The pixel data as unsigned bytes, four per pixel, as red, green, blue, alpha.
+    /// The bytes are reinterpreted, such that `-1` in the `pixel_data` results in `255`.
+    pub fn to_u8_rgba(&self) -> Vec<u8> {
+        self.pixel_data.iter().map(|&byte| byte as u8).collect()
+    }
+
+    /// The red, green, blue, and alpha values of the pixel at the specified position.
+    /// The position `(0, 0)` is the top left pixel, as the lines are stored from top to bottom,
+    /// and each line from left to right.
+    /// Returns `None` if the position is outside of the preview, or if the pixel data is too short.
+    pub fn pixel(&self, position: Vec2<usize>) -> Option<(u8, u8, u8, u8)> {
+        if position.x() >= self.size.width() || position.y() >= self.size.height() {
+            return None;
+        }
+
+        let index = (position.y() * self.size.width() + position.x()) * 4;
+        match self.pixel_data.get(index .. index + 4)? {
+            &[red, green, blue, alpha] => Some((red as u8, green as u8, blue as u8, alpha as u8)),
+            _ => None,
+        }
+    }
+
     /// Number of bytes this would consume in an exr file.
     pub fn byte_size(&self) -> usize {
         2 * u32::BYTE_SIZE + self.pixel_data.len()
@@ -1610,6 +2016,15 @@ impl TileDescription {
 
         Ok(())
     }
+
+    /// The number of tiles in each dimension that are required to cover the window at full resolution.
+    /// Tiles at the right and bottom edge may be only partially used.
+    pub fn tiles_in_window(&self, window: IntegerBounds) -> Vec2<usize> {
+        Vec2(
+            compute_block_count(window.size.width(), self.tile_size.width()),
+            compute_block_count(window.size.height(), self.tile_size.height()),
+        )
+    }
 }
 
 
@@ -1627,18 +2042,51 @@ pub fn write<W: Write>(name: &TextSlice, value: &AttributeValue, write: &mut W)
     Text::write_null_terminated_bytes(name, write)?;
     Text::write_null_terminated_bytes(value.kind_name(), write)?;
     i32::write(value.byte_size() as i32, write)?;
-    value.write(write)
+
+    // the size written above must match the value, otherwise all following bytes would be misinterpreted
+    let mut tracking = Tracking::new(write);
+    value.write(&mut tracking)?;
+
+    debug_assert_eq!(
+        tracking.byte_position(), value.byte_size(),
+        "attribute byte size bug for {:?}", Text::from_slice_unchecked(name)
+    );
+
+    Ok(())
 }
 
 /// Read the attribute without validating. The result may be `Ok` even if this single attribute is invalid.
 pub fn read(read: &mut PeekRead<impl Read>, max_size: usize) -> Result<(Text, Result<AttributeValue>)> {
+    read_with_registry(read, max_size, None)
+}
+
+/// Read the attribute without validating, parsing its value with the registry if one is specified.
+/// The result may be `Ok` even if this single attribute is invalid.
+pub fn read_with_registry(
+    read: &mut PeekRead<impl Read>, max_size: usize, registry: Option<&AttributeTypeRegistry>
+) -> Result<(Text, Result<AttributeValue>)> {
     let name = Text::read_null_terminated(read, max_size)?;
-    let kind = Text::read_null_terminated(read, max_size)?;
+    let kind = read_type_name(read, max_size)?;
     let size = i32_to_usize(i32::read(read)?, "attribute size")?;
-    let value = AttributeValue::read(read, kind, size)?;
+
+    let value = match registry {
+        Some(registry) => AttributeValue::read_with_registry(read, kind, size, registry)?,
+        None => AttributeValue::read(read, kind, size)?,
+    };
+
     Ok((name, value))
 }
 
+/// Read the name of the type of an attribute.
+/// Type names are subject to the same length limit as attribute names,
+/// which depends on whether the file has long names.
+fn read_type_name(read: &mut impl Read, max_size: usize) -> Result<Text> {
+    Text::read_null_terminated(read, max_size).map_err(|error| match error {
+        Error::Invalid(message) if message == "text too long" => Error::invalid("attribute type name too long"),
+        error => error,
+    })
+}
+
 /// Validate this attribute.
 pub fn validate(name: &Text, value: &AttributeValue, long_names: &mut bool, allow_sampling: bool, data_window: IntegerBounds, strict: bool) -> UnitResult {
     name.validate(true, Some(long_names))?; // only name text has length restriction
@@ -1646,6 +2094,63 @@ pub fn validate(name: &Text, value: &AttributeValue, long_names: &mut bool, allo
 }
 
 
+/// An attribute whose value has not been parsed yet.
+/// Remembers where the value is located in the byte stream,
+/// so that only the values that are actually needed have to be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LazyAttribute {
+
+    /// The name of the attribute.
+    pub name: Text,
+
+    /// The name of the type of the attribute value.
+    pub kind: Text,
+
+    /// The position of the first byte of the value, counted from the start of the byte stream.
+    pub value_byte_position: usize,
+
+    /// Number of bytes of the value.
+    pub byte_size: usize,
+}
+
+impl LazyAttribute {
+
+    /// Read the name, type, and size of the next attribute, skipping its value.
+    pub fn read(read: &mut PeekRead<Tracking<impl Read>>, max_size: usize) -> Result<Self> {
+        let name = Text::read_null_terminated(read, max_size)?;
+        let kind = read_type_name(read, max_size)?;
+        let byte_size = i32_to_usize(i32::read(read)?, "attribute size")?;
+        let value_byte_position = read.byte_position();
+
+        skip_bytes(read, byte_size).map_err(|error| match Error::from(error) {
+            Error::Invalid(_) => Error::invalid("attribute size exceeds file"),
+            other => other,
+        })?;
+
+        Ok(LazyAttribute { name, kind, value_byte_position, byte_size })
+    }
+
+    /// Read all attributes of a single header, without parsing any values.
+    /// Consumes the null byte that terminates the header.
+    pub fn read_header(read: &mut PeekRead<Tracking<impl Read>>, max_size: usize) -> Result<Vec<Self>> {
+        let mut attributes = Vec::new();
+
+        while !sequence_end::has_come(read)? {
+            attributes.push(Self::read(read, max_size)?);
+        }
+
+        Ok(attributes)
+    }
+
+    /// Seek to the value of this attribute and parse it, without validating.
+    /// The byte stream must be the same that this attribute was read from.
+    pub fn value(&self, read: &mut (impl Read + Seek)) -> Result<AttributeValue> {
+        read.seek(SeekFrom::Start(usize_to_u64(self.value_byte_position)))?;
+        AttributeValue::read(&mut PeekRead::new(read), self.kind.clone(), self.byte_size)?
+    }
+}
+
+
 impl AttributeValue {
 
     /// Number of bytes this would consume in an exr file.
@@ -1667,6 +2172,8 @@ impl AttributeValue {
             FloatVec2(_) => { 2 * f32::BYTE_SIZE },
             IntVec3(_) => { 3 * i32::BYTE_SIZE },
             FloatVec3(_) => { 3 * f32::BYTE_SIZE },
+            F64Vec2(_) => { 2 * f64::BYTE_SIZE },
+            F64Vec3(_) => { 3 * f64::BYTE_SIZE },
 
             ChannelList(ref channels) => channels.byte_size(),
             Chromaticities(_) => self::Chromaticities::byte_size(),
@@ -1709,6 +2216,8 @@ impl AttributeValue {
             FloatVec2(_) => ty::F32VEC2,
             IntVec3(_) => ty::I32VEC3,
             FloatVec3(_) => ty::F32VEC3,
+            F64Vec2(_) => ty::F64VEC2,
+            F64Vec3(_) => ty::F64VEC3,
             ChannelList(_) =>  ty::CHANNEL_LIST,
             Chromaticities(_) =>  ty::CHROMATICITIES,
             Compression(_) =>  ty::COMPRESSION,
@@ -1744,6 +2253,8 @@ impl AttributeValue {
             FloatVec2(Vec2(x, y)) => { x.write(write)?; y.write(write)?; },
             IntVec3((x, y, z)) => { x.write(write)?; y.write(write)?; z.write(write)?; },
             FloatVec3((x, y, z)) => { x.write(write)?; y.write(write)?; z.write(write)?; },
+            F64Vec2(Vec2(x, y)) => { x.write(write)?; y.write(write)?; },
+            F64Vec3((x, y, z)) => { x.write(write)?; y.write(write)?; z.write(write)?; },
 
             ChannelList(ref channels) => channels.write(write)?,
             Chromaticities(ref value) => value.write(write)?,
@@ -1776,18 +2287,65 @@ impl AttributeValue {
     /// Returns `Ok(Err(Error))` for invalid attributes from a valid byte source.
     /// Returns `Err(Error)` for invalid byte sources, for example for invalid files.
     pub fn read(read: &mut PeekRead<impl Read>, kind: Text, byte_size: usize) -> Result<Result<Self>> {
+        // always read bytes
+        let attribute_bytes = Self::read_bytes(read, byte_size)?;
+        // TODO no allocation for small attributes // : SmallVec<[u8; 64]> = smallvec![0; byte_size];
+
+        Ok(Self::parse_built_in(kind, attribute_bytes))
+    }
+
+    /// Read the value without validating, parsing it with the functions of the registry.
+    /// Types that are not contained in the registry are returned as `AttributeValue::Custom`.
+    /// Returns `Ok(Ok(attribute))` for valid attributes.
+    /// Returns `Ok(Err(Error))` for invalid attributes from a valid byte source.
+    /// Returns `Err(Error)` for invalid byte sources, for example for invalid files.
+    pub fn read_with_registry(
+        read: &mut PeekRead<impl Read>, kind: Text, byte_size: usize, registry: &AttributeTypeRegistry
+    ) -> Result<Result<Self>> {
+        let attribute_bytes = Self::read_bytes(read, byte_size)?;
+        Ok(registry.parse(kind, attribute_bytes))
+    }
+
+    /// Read the raw bytes of an attribute value, also for types that are not known.
+    /// The byte source cannot seek, so a bogus size is only detected when the stream ends early.
+    /// Reading in small portions prevents allocating the whole claimed size up front.
+    fn read_bytes(read: &mut PeekRead<impl Read>, byte_size: usize) -> Result<Vec<u8>> {
+        u8::read_vec(read, byte_size, 128, None, "attribute value size").map_err(|error| match error {
+            Error::Invalid(_) => Error::invalid("attribute size exceeds file"),
+            other => other,
+        })
+    }
+
+    /// Parse the bytes of an attribute value with one of the types defined by the specification.
+    /// Returns `AttributeValue::Custom` for all other types.
+    fn parse_built_in(kind: Text, attribute_bytes: Vec<u8>) -> Result<Self> {
         use self::AttributeValue::*;
         use self::type_names as ty;
 
-        // always read bytes
-        let attribute_bytes = u8::read_vec(read, byte_size, 128, None, "attribute value size")?;
-        // TODO no allocation for small attributes // : SmallVec<[u8; 64]> = smallvec![0; byte_size];
+        let byte_size = attribute_bytes.len();
+
+        // only texts may be empty, all other built-in types contain at least one byte
+        let may_be_empty = kind.as_slice() == ty::TEXT || kind.as_slice() == ty::TEXT_VECTOR;
+        if byte_size == 0 && !may_be_empty && ty::ALL.contains(&kind.as_slice()) {
+            return Err(Error::invalid(format!("empty `{}` attribute value", kind)));
+        }
 
         let parse_attribute = move || {
             let reader = &mut attribute_bytes.as_slice();
 
             Ok(match kind.bytes.as_slice() {
-                ty::I32BOX2 => IntegerBounds(self::IntegerBounds::read(reader)?),
+                ty::I32BOX2 => {
+                    let bounds = self::IntegerBounds::read(reader)?;
+
+                    // a box with its maximum further left than its minimum is read as an empty box,
+                    // so the original bytes are kept instead, allowing strict validation to reject the box,
+                    // and allowing the box to be written unchanged
+                    let mut normalized = Vec::with_capacity(self::IntegerBounds::byte_size());
+                    bounds.write(&mut normalized)?;
+
+                    if attribute_bytes.starts_with(&normalized) { IntegerBounds(bounds) }
+                    else { Custom { kind: kind.clone(), bytes: attribute_bytes.clone() } }
+                },
                 ty::F32BOX2 => FloatRect(self::FloatRect::read(reader)?),
 
                 ty::I32 => I32(i32::read(reader)?),
@@ -1828,6 +2386,19 @@ impl AttributeValue {
                     (a, b, c)
                 }),
 
+                ty::F64VEC2 => F64Vec2({
+                    let a = f64::read(reader)?;
+                    let b = f64::read(reader)?;
+                    Vec2(a, b)
+                }),
+
+                ty::F64VEC3 => F64Vec3({
+                    let a = f64::read(reader)?;
+                    let b = f64::read(reader)?;
+                    let c = f64::read(reader)?;
+                    (a, b, c)
+                }),
+
                 ty::CHANNEL_LIST    => ChannelList(self::ChannelList::read(&mut PeekRead::new(attribute_bytes.as_slice()))?),
                 ty::CHROMATICITIES  => Chromaticities(self::Chromaticities::read(reader)?),
                 ty::COMPRESSION     => Compression(self::Compression::read(reader)?),
@@ -1863,7 +2434,7 @@ impl AttributeValue {
             })
         };
 
-        Ok(parse_attribute())
+        parse_attribute()
     }
 
     /// Validate this instance.
@@ -1873,6 +2444,19 @@ impl AttributeValue {
         match *self {
             ChannelList(ref channels) => channels.validate(allow_sampling, data_window, strict)?,
             TileDescription(ref value) => value.validate()?,
+            IntegerBounds(ref value) => {
+                value.validate(None)?;
+
+                if strict && (value.size.x() == 0 || value.size.y() == 0) {
+                    return Err(Error::invalid("box minimum larger than maximum"));
+                }
+            },
+
+            Custom { ref kind, .. } => if strict && kind.as_slice() == type_names::I32BOX2 {
+                return Err(Error::invalid("box minimum larger than maximum"));
+            },
+
+            FloatRect(ref value) => value.validate(strict)?,
             Preview(ref value) => value.validate(strict)?,
             TimeCode(ref time_code) => time_code.validate(strict)?,
 
@@ -1903,6 +2487,14 @@ impl AttributeValue {
         }
     }
 
+    /// Return `Ok(f64)` if this attribute is an f64.
+    pub fn to_f64(&self) -> Result<f64> {
+        match *self {
+            AttributeValue::F64(value) => Ok(value),
+            _ => Err(invalid_type())
+        }
+    }
+
     /// Return `Ok(Text)` if this attribute is a text.
     pub fn into_text(self) -> Result<Text> {
         match self {
@@ -1934,14 +2526,142 @@ impl AttributeValue {
             _ => Err(invalid_type())
         }
     }
+
+    /// Return `Ok(RationalParts)` if this attribute is a rational.
+    pub fn to_rational_parts(&self) -> Result<RationalParts> {
+        match *self {
+            AttributeValue::Rational(value) => Ok(RationalParts::new(value)),
+            _ => Err(invalid_type())
+        }
+    }
+
+    /// Return `Ok(EnvironmentMap)` if this attribute is an environment map.
+    pub fn to_environment_map(&self) -> Result<EnvironmentMap> {
+        match *self {
+            AttributeValue::EnvironmentMap(value) => Ok(value),
+            _ => Err(invalid_type())
+        }
+    }
+
+    /// Return `Ok(LineOrder)` if this attribute is a line order.
+    pub fn to_line_order(&self) -> Result<LineOrder> {
+        match *self {
+            AttributeValue::LineOrder(value) => Ok(value),
+            _ => Err(invalid_type())
+        }
+    }
+
+    /// Return `Ok(KeyCode)` if this attribute is a key code.
+    pub fn to_key_code(&self) -> Result<KeyCode> {
+        match *self {
+            AttributeValue::KeyCode(value) => Ok(value),
+            _ => Err(invalid_type())
+        }
+    }
+
+    /// Return `Ok(TileDescription)` if this attribute is a tile description.
+    pub fn to_tile_description(&self) -> Result<TileDescription> {
+        match *self {
+            AttributeValue::TileDescription(value) => Ok(value),
+            _ => Err(invalid_type())
+        }
+    }
+}
+
+
+
+/// A function that parses the bytes of an attribute value of a specific type.
+pub type ParseAttributeValue = dyn Fn(&[u8]) -> Result<AttributeValue> + Send + Sync;
+
+/// Maps attribute type names to functions that parse the bytes of an attribute value.
+/// Allows parsing custom attribute types, such as studio-specific types,
+/// which would otherwise be returned as `AttributeValue::Custom`.
+/// The default registry contains all types defined by the specification.
+pub struct AttributeTypeRegistry {
+    parsers: std::collections::HashMap<Text, Box<ParseAttributeValue>>,
+}
+
+impl AttributeTypeRegistry {
+
+    /// A registry without any types. All attributes will be returned as `AttributeValue::Custom`.
+    pub fn empty() -> Self {
+        Self { parsers: Default::default() }
+    }
+
+    /// A registry containing all types defined by the specification.
+    pub fn with_built_in_types() -> Self {
+        let mut registry = Self::empty();
+
+        for &type_name in type_names::ALL {
+            let kind = Text::from_slice_unchecked(type_name);
+            let parse_kind = kind.clone();
+
+            registry.register(kind, move |bytes: &[u8]| {
+                AttributeValue::parse_built_in(parse_kind.clone(), bytes.to_vec())
+            });
+        }
+
+        registry
+    }
+
+    /// Parse attributes with the specified type name using the specified function.
+    /// Replaces any function previously registered for this type, including built-in types.
+    pub fn register(&mut self, type_name: impl Into<Text>, parse: impl Fn(&[u8]) -> Result<AttributeValue> + Send + Sync + 'static) {
+        self.parsers.insert(type_name.into(), Box::new(parse));
+    }
+
+    /// Parse attributes with the specified type name using the specified function.
+    /// Replaces any function previously registered for this type, including built-in types.
+    pub fn with_type(mut self, type_name: impl Into<Text>, parse: impl Fn(&[u8]) -> Result<AttributeValue> + Send + Sync + 'static) -> Self {
+        self.register(type_name, parse);
+        self
+    }
+
+    /// Whether a function is registered for the specified type name.
+    pub fn contains(&self, type_name: &TextSlice) -> bool {
+        self.parsers.contains_key(type_name)
+    }
+
+    /// Parse the bytes of an attribute value using the function registered for its type.
+    /// Returns `AttributeValue::Custom` if no function is registered for the type.
+    pub fn parse(&self, kind: Text, bytes: Vec<u8>) -> Result<AttributeValue> {
+        match self.parsers.get(&kind) {
+            Some(parse) => parse(&bytes),
+            None => Ok(AttributeValue::Custom { kind, bytes }),
+        }
+    }
+
+    /// Parse an `AttributeValue::Custom` that has already been read, if its type is registered.
+    /// Returns all other attributes unchanged.
+    pub fn resolve(&self, value: AttributeValue) -> Result<AttributeValue> {
+        match value {
+            AttributeValue::Custom { kind, bytes } => self.parse(kind, bytes),
+            value => Ok(value),
+        }
+    }
 }
 
+impl Default for AttributeTypeRegistry {
+    fn default() -> Self { Self::with_built_in_types() }
+}
+
+impl std::fmt::Debug for AttributeTypeRegistry {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.debug_set().entries(self.parsers.keys()).finish()
+    }
+}
 
 
 /// Contains string literals identifying the type of an attribute.
 pub mod type_names {
     macro_rules! define_attribute_type_names {
         ( $($name: ident : $value: expr),* ) => {
+
+            /// A list containing the names of all types defined by the specification.
+            pub const ALL: &'static [&'static [u8]] = &[
+                $( $value ),*
+            ];
+
             $(
                 /// The byte-string name of this attribute type as it appears in an exr file.
                 pub const $name: &'static [u8] = $value;
@@ -1961,6 +2681,8 @@ pub mod type_names {
         F32VEC2:        b"v2f",
         I32VEC3:        b"v3i",
         F32VEC3:        b"v3f",
+        F64VEC2:        b"v2d",
+        F64VEC3:        b"v3d",
         CHANNEL_LIST:   b"chlist",
         CHROMATICITIES: b"chromaticities",
         COMPRESSION:    b"compression",
@@ -2004,6 +2726,10 @@ mod test {
         assert_eq!(round_up.divide(10, 3), 4, "round up");
         assert_eq!(round_up.divide(100, 50), 2, "divide even");
         assert_eq!(round_up.divide(100, 49), 3, "round up");
+
+        assert_eq!(round_up.divide(u32::MAX, 2), u32::MAX / 2 + 1, "round up without overflow");
+        assert_eq!(round_up.divide(u32::MAX, u32::MAX), 1, "divide equal without overflow");
+        assert_eq!(round_up.divide(usize::MAX, 1), usize::MAX, "divide by one without overflow");
     }
 
     #[test]
@@ -2062,6 +2788,14 @@ mod test {
                 Text::from("leg count"),
                 AttributeValue::F64(9.114939599234),
             ),
+            (
+                Text::from("camera position"),
+                AttributeValue::F64Vec3((-1.5e300, 0.000_000_1, 9.114939599234)),
+            ),
+            (
+                Text::from("camera offset"),
+                AttributeValue::F64Vec2(Vec2(3.0, -7.25)),
+            ),
             (
                 Text::from("rabbit area"),
                 AttributeValue::FloatRect(FloatRect {
@@ -2164,6 +2898,687 @@ mod test {
         }
     }
 
+    #[test]
+    fn byte_size_matches_written_bytes_for_every_kind(){
+        let values = vec![
+            AttributeValue::ChannelList(ChannelList::new(smallvec![
+                ChannelDescription::named("B", SampleType::F16),
+                ChannelDescription::named("depth", SampleType::F32),
+            ])),
+            AttributeValue::Chromaticities(Chromaticities::ACES_AP0),
+            AttributeValue::Compression(Compression::PIZ),
+            AttributeValue::EnvironmentMap(EnvironmentMap::LatitudeLongitude),
+            AttributeValue::KeyCode(KeyCode {
+                film_manufacturer_code: 1, film_type: 2, film_roll_prefix: 3, count: 4,
+                perforation_offset: 5, perforations_per_frame: 6, perforations_per_count: 7,
+            }),
+            AttributeValue::LineOrder(LineOrder::Unspecified),
+            AttributeValue::Matrix3x3([0.5; 9]),
+            AttributeValue::Matrix4x4([-2.0; 16]),
+            AttributeValue::Preview(Preview::from_u8_rgba((3, 2), vec![255; 3 * 2 * 4])),
+            AttributeValue::Rational((24000, 1001)),
+            AttributeValue::BlockType(BlockType::DeepScanLine),
+            AttributeValue::TextVector(vec![ Text::from("left"), Text::from(""), Text::from("right eye") ]),
+            AttributeValue::TileDescription(TileDescription {
+                tile_size: Vec2(64, 32), level_mode: LevelMode::RipMap, rounding_mode: RoundingMode::Down,
+            }),
+            AttributeValue::TimeCode(TimeCode {
+                hours: 11, minutes: 42, seconds: 13, frame: 7,
+                drop_frame: true, color_frame: false, field_phase: true,
+                binary_group_flags: [true, false, true], binary_groups: [1, 2, 3, 4, 5, 6, 7, 0],
+            }),
+            AttributeValue::Text(Text::from("a string without null termination")),
+            AttributeValue::Text(Text::from("")),
+            AttributeValue::F64(-0.125),
+            AttributeValue::F32(3.5),
+            AttributeValue::I32(-7),
+            AttributeValue::IntegerBounds(IntegerBounds::new((-3, 4), (10, 2))),
+            AttributeValue::FloatRect(FloatRect { min: Vec2(0.0, -1.0), max: Vec2(1.0, 2.0) }),
+            AttributeValue::IntVec2(Vec2(1, -1)),
+            AttributeValue::FloatVec2(Vec2(0.25, 4.0)),
+            AttributeValue::IntVec3((1, 2, 3)),
+            AttributeValue::FloatVec3((0.1, 0.2, 0.3)),
+            AttributeValue::F64Vec2(Vec2(1.0e-300, 2.0)),
+            AttributeValue::F64Vec3((1.0, 2.0, 3.0)),
+            AttributeValue::Custom { kind: Text::from("carrot"), bytes: vec![1, 2, 3, 4, 5] },
+        ];
+
+        // fails to compile if a kind of attribute is added, so that it cannot be forgotten here
+        fn kind_index(value: &AttributeValue) -> usize {
+            use AttributeValue::*;
+            match value {
+                ChannelList(_) => 0, Chromaticities(_) => 1, Compression(_) => 2, EnvironmentMap(_) => 3,
+                KeyCode(_) => 4, LineOrder(_) => 5, Matrix3x3(_) => 6, Matrix4x4(_) => 7, Preview(_) => 8,
+                Rational(_) => 9, BlockType(_) => 10, TextVector(_) => 11, TileDescription(_) => 12,
+                TimeCode(_) => 13, Text(_) => 14, F64(_) => 15, F32(_) => 16, I32(_) => 17,
+                IntegerBounds(_) => 18, FloatRect(_) => 19, IntVec2(_) => 20, FloatVec2(_) => 21,
+                IntVec3(_) => 22, FloatVec3(_) => 23, F64Vec2(_) => 24, F64Vec3(_) => 25, Custom { .. } => 26,
+            }
+        }
+
+        let mut covered = [false; 27];
+        for value in &values {
+            covered[kind_index(value)] = true;
+
+            let mut bytes = Vec::new();
+            value.write(&mut bytes).unwrap();
+            assert_eq!(value.byte_size(), bytes.len(), "byte size of {:?}", value);
+
+            let mut attribute_bytes = Vec::new();
+            super::write(b"name", value, &mut attribute_bytes).unwrap();
+            assert_eq!(super::byte_size(&Text::from("name"), value), attribute_bytes.len(), "attribute byte size of {:?}", value);
+        }
+
+        assert!(covered.iter().all(|&covered| covered), "not all kinds of attributes were tested");
+    }
+
+    #[test]
+    fn typed_accessors(){
+        let environment_map = AttributeValue::EnvironmentMap(EnvironmentMap::Cube);
+        assert_eq!(environment_map.to_environment_map().unwrap(), EnvironmentMap::Cube);
+        assert!(environment_map.to_line_order().is_err());
+
+        let line_order = AttributeValue::LineOrder(LineOrder::Decreasing);
+        assert_eq!(line_order.to_line_order().unwrap(), LineOrder::Decreasing);
+        assert!(line_order.to_environment_map().is_err());
+
+        let key_code = KeyCode {
+            film_manufacturer_code: 1, film_type: 2, film_roll_prefix: 3, count: 4,
+            perforation_offset: 5, perforations_per_frame: 6, perforations_per_count: 7,
+        };
+
+        let key_code_attribute = AttributeValue::KeyCode(key_code);
+        assert_eq!(key_code_attribute.to_key_code().unwrap(), key_code);
+        assert!(key_code_attribute.to_tile_description().is_err());
+
+        let tiles = TileDescription {
+            tile_size: Vec2(32, 16),
+            level_mode: LevelMode::MipMap,
+            rounding_mode: RoundingMode::Up,
+        };
+
+        let tiles_attribute = AttributeValue::TileDescription(tiles);
+        assert_eq!(tiles_attribute.to_tile_description().unwrap(), tiles);
+        assert!(tiles_attribute.to_key_code().is_err());
+        assert!(AttributeValue::I32(3).to_tile_description().is_err());
+    }
+
+    #[test]
+    fn rational_parts(){
+        let frame_rate = AttributeValue::Rational((30000, 1001)).to_rational_parts().unwrap();
+        assert!((frame_rate.to_f64().unwrap() - 29.97).abs() < 0.001);
+        assert_eq!(frame_rate.reduced(), frame_rate, "already reduced");
+
+        let reducible = RationalParts::new((-60000, 2002));
+        assert_eq!(reducible.reduced(), RationalParts { numerator: -30000, denominator: 1001 });
+        assert_eq!(RationalParts::new((0, 24)).reduced().to_rational(), (0, 1));
+        assert_eq!(RationalParts::new((i32::MIN, 2)).reduced().to_rational(), (i32::MIN / 2, 1));
+
+        let invalid = RationalParts::new((24, 0));
+        assert_eq!(invalid.to_f64(), None);
+        assert_eq!(invalid.reduced(), invalid);
+
+        assert!(AttributeValue::F32(24.0).to_rational_parts().is_err());
+    }
+
+    #[test]
+    fn empty_box_has_zero_size(){
+        let empty = IntegerBounds::new((5, -3), (0, 4));
+
+        let mut bytes = Vec::new();
+        empty.write(&mut bytes).unwrap();
+
+        let read = IntegerBounds::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, empty);
+        assert_eq!(read.size.area(), 0);
+    }
+
+    #[test]
+    fn read_short_text_without_heap_allocation(){
+        let name = Text::from("ten bytes!");
+
+        let mut sized = Vec::new();
+        name.write_i32_sized(&mut sized).unwrap();
+        let read_sized = Text::read_i32_sized(&mut sized.as_slice(), 1024).unwrap();
+        assert_eq!(read_sized, name);
+        assert!(!read_sized.bytes.spilled(), "short sized text was allocated on the heap");
+
+        let mut terminated = Vec::new();
+        name.write_null_terminated(&mut terminated).unwrap();
+        let read_terminated = Text::read_null_terminated(&mut terminated.as_slice(), 255).unwrap();
+        assert_eq!(read_terminated, name);
+        assert!(!read_terminated.bytes.spilled(), "short null-terminated text was allocated on the heap");
+
+        let long = Text::from("a name that is too long to be stored inline");
+        let mut long_bytes = Vec::new();
+        long.write_i32_sized(&mut long_bytes).unwrap();
+        assert_eq!(Text::read_i32_sized(&mut long_bytes.as_slice(), 1024).unwrap(), long);
+        assert!(Text::read_i32_sized(&mut long_bytes.as_slice(), 8).is_err());
+    }
+
+    #[test]
+    fn zero_sized_attribute_values(){
+        let read_empty = |kind: &str| AttributeValue::read(
+            &mut PeekRead::new([].as_ref()), Text::from(kind), 0
+        ).unwrap();
+
+        assert_eq!(read_empty("stringvector").unwrap(), AttributeValue::TextVector(Vec::new()));
+        assert_eq!(read_empty("string").unwrap(), AttributeValue::Text(Text::from("")));
+        assert_eq!(read_empty("custom").unwrap(), AttributeValue::Custom { kind: Text::from("custom"), bytes: Vec::new() });
+
+        match read_empty("int") {
+            Err(Error::Invalid(message)) => assert_eq!(message, "empty `int` attribute value"),
+            other => panic!("empty integer not rejected: {:?}", other),
+        }
+
+        // the bytes of the next attribute are not consumed
+        let mut bytes = PeekRead::new([1_u8, 2, 3].as_ref());
+        assert!(AttributeValue::read(&mut bytes, Text::from("box2i"), 0).unwrap().is_err());
+        assert_eq!(u8::read(&mut bytes).unwrap(), 1);
+    }
+
+    #[test]
+    fn reject_text_vector_element_exceeding_attribute(){
+        let mut bytes = Vec::new();
+        1_000_000_i32.write(&mut bytes).unwrap();
+        u8::write_slice(&mut bytes, b"left").unwrap();
+
+        // the first text claims more bytes than the whole attribute, and the whole file, contains
+        let element_too_large = AttributeValue::read(
+            &mut PeekRead::new(bytes.as_slice()), Text::from("stringvector"), bytes.len()
+        ).unwrap();
+
+        match element_too_large {
+            Err(Error::Invalid(message)) => assert_eq!(message, "text attribute length"),
+            other => panic!("oversized text vector element not rejected: {:?}", other),
+        }
+
+        // the second text claims the bytes of the first text, which have already been read
+        let mut bytes = Vec::new();
+        Text::write_vec_of_i32_sized_texts(&mut bytes, &[ Text::from("left"), Text::from("right") ]).unwrap();
+        bytes[8 .. 12].copy_from_slice(&9_i32.to_le_bytes());
+
+        let overlapping = AttributeValue::read(
+            &mut PeekRead::new(bytes.as_slice()), Text::from("stringvector"), bytes.len()
+        ).unwrap();
+
+        assert!(overlapping.is_err());
+    }
+
+    #[test]
+    fn validate_rectangle_attributes(){
+        let inverted = FloatRect { min: Vec2(1.0, 0.0), max: Vec2(-1.0, 2.0) };
+        let mut bytes = Vec::new();
+        inverted.write(&mut bytes).unwrap();
+
+        // both the direct reader and the attribute reader use `FloatRect::read`
+        let value = AttributeValue::read(
+            &mut PeekRead::new(bytes.as_slice()), Text::from("box2f"), bytes.len()
+        ).unwrap().unwrap();
+
+        assert_eq!(value, AttributeValue::FloatRect(FloatRect::read(&mut bytes.as_slice()).unwrap()));
+
+        let data_window = IntegerBounds::from_dimensions((4, 4));
+        assert!(value.validate(true, data_window, false).is_ok());
+
+        match value.validate(true, data_window, true) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "rectangle minimum larger than maximum"),
+            other => panic!("inverted rectangle not rejected: {:?}", other),
+        }
+
+        let huge = AttributeValue::IntegerBounds(IntegerBounds::new((0, 0), (i32::MAX as usize, 1)));
+        assert!(huge.validate(true, data_window, false).is_err());
+
+        // an integer box with its minimum right of its maximum
+        let mut empty_bytes = Vec::new();
+        for value in &[ 3_i32, 0, 2, 5 ] { value.write(&mut empty_bytes).unwrap(); }
+
+        let mut inverted_bytes = Vec::new();
+        for value in &[ 3_i32, 0, -7, 5 ] { value.write(&mut inverted_bytes).unwrap(); }
+
+        for bytes in &[ empty_bytes, inverted_bytes ] {
+            let value = AttributeValue::read(
+                &mut PeekRead::new(bytes.as_slice()), Text::from("box2i"), bytes.len()
+            ).unwrap().unwrap();
+
+            assert!(value.validate(true, data_window, false).is_ok());
+
+            match value.validate(true, data_window, true) {
+                Err(Error::Invalid(message)) => assert_eq!(message, "box minimum larger than maximum"),
+                other => panic!("inverted integer box not rejected: {:?}", other),
+            }
+
+            let mut written = Vec::new();
+            value.write(&mut written).unwrap();
+            assert_eq!(&written, bytes, "inverted integer box changed when writing");
+        }
+    }
+
+    #[test]
+    fn text_validation_errors_propagate_as_read_errors(){
+        fn read_validated(mut bytes: &[u8]) -> Result<Text> {
+            let text = Text::read_i32_sized(&mut bytes, 256)?;
+            text.validate(true, Some(&mut false))?;
+            Ok(text)
+        }
+
+        assert_eq!(read_validated(b"\x04\0\0\0name").unwrap(), Text::from("name"));
+
+        match read_validated(b"\0\0\0\0") {
+            Err(Error::Invalid(message)) => assert_eq!(message, "text must not be empty"),
+            other => panic!("empty text not rejected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reject_unterminated_and_overlong_text(){
+        match Text::read_null_terminated(&mut b"rabbit".as_ref(), 256) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "unterminated text"),
+            other => panic!("unterminated text not rejected: {:?}", other),
+        }
+
+        match Text::read_null_terminated(&mut [].as_ref(), 256) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "unterminated text"),
+            other => panic!("missing text not rejected: {:?}", other),
+        }
+
+        let mut longest = vec![ b'x'; 255 ];
+        longest.push(0);
+        assert_eq!(Text::read_null_terminated(&mut longest.as_slice(), usize::MAX).unwrap().as_slice(), &longest[.. 255]);
+
+        let mut too_long = vec![ b'x'; 256 ];
+        too_long.push(0);
+
+        match Text::read_null_terminated(&mut too_long.as_slice(), usize::MAX) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "text too long"),
+            other => panic!("overlong text not rejected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_text_rejects_chars_outside_latin_1(){
+        let text: Text = "bunny".parse().unwrap();
+        assert_eq!(text, Text::from("bunny"));
+
+        // a two-byte utf-8 char that is still a single latin-1 byte
+        let latin: Text = "caf\u{e9}".parse().unwrap();
+        assert_eq!(latin.as_slice(), &[b'c', b'a', b'f', 0xE9]);
+
+        // a two-byte utf-8 char outside of latin-1
+        match "\u{151}".parse::<Text>() {
+            Err(Error::Invalid(message)) => assert_eq!(message, "text contains unsupported characters"),
+            other => panic!("unsupported char not rejected: {:?}", other),
+        }
+
+        assert_eq!(Text::new_or_none("\u{151}"), None);
+        assert!(std::panic::catch_unwind(|| Text::new_or_panic("\u{151}")).is_err());
+    }
+
+    #[test]
+    fn cube_face_windows_are_stacked_vertically(){
+        let data_window = IntegerBounds::new((-3, 10), (8, 48));
+
+        let first = EnvironmentMap::cube_face_window(0, data_window).unwrap();
+        assert_eq!(first, IntegerBounds::new((-3, 10), (8, 8)));
+
+        let last = EnvironmentMap::cube_face_window(5, data_window).unwrap();
+        assert_eq!(last, IntegerBounds::new((-3, 50), (8, 8)));
+        assert_eq!(last.end(), data_window.end());
+
+        for face in 1 .. EnvironmentMap::CUBE_FACE_COUNT {
+            let previous = EnvironmentMap::cube_face_window(face - 1, data_window).unwrap();
+            let current = EnvironmentMap::cube_face_window(face, data_window).unwrap();
+            assert_eq!(previous.end().y(), current.position.y(), "faces must not overlap");
+        }
+
+        assert!(EnvironmentMap::cube_face_window(6, data_window).is_err());
+        assert!(EnvironmentMap::cube_face_window(0, IntegerBounds::new((-3, 10), (8, 47))).is_err());
+        assert!(EnvironmentMap::cube_face_window(0, IntegerBounds::new((0, 0), (8, 8))).is_err());
+
+        // the last face would start beyond the range of `i32`
+        let huge = IntegerBounds::new((0, i32::MAX - 10), (1 << 28, 6 << 28));
+        assert!(EnvironmentMap::cube_face_window(5, huge).is_err());
+    }
+
+    #[test]
+    fn block_type_from_text_bytes(){
+        let kinds = [
+            (block_type_strings::SCAN_LINE, BlockType::ScanLine, false, false),
+            (block_type_strings::TILE, BlockType::Tile, false, true),
+            (block_type_strings::DEEP_SCAN_LINE, BlockType::DeepScanLine, true, false),
+            (block_type_strings::DEEP_TILE, BlockType::DeepTile, true, true),
+        ];
+
+        for &(bytes, kind, deep, tiled) in &kinds {
+            assert_eq!(BlockType::from_text_bytes(bytes), Some(kind));
+            assert_eq!(BlockType::parse(Text::from_slice_unchecked(bytes)).unwrap(), kind);
+            assert_eq!(BlockType::new(deep, tiled), kind);
+            assert_eq!((kind.is_deep(), kind.is_tiled()), (deep, tiled));
+            assert_eq!(kind.to_text_bytes(), bytes);
+        }
+
+        assert_eq!(BlockType::from_text_bytes(b"deepscanlineimage"), None);
+        assert!(BlockType::parse(Text::from("tiles")).is_err());
+    }
+
+    #[test]
+    fn validate_chromaticities(){
+        let rec709 = Chromaticities {
+            red: Vec2(0.64, 0.33),
+            green: Vec2(0.3, 0.6),
+            blue: Vec2(0.15, 0.06),
+            white: Vec2(0.3127, 0.329),
+        };
+
+        assert!(rec709.validate().is_ok());
+        assert!(Chromaticities::ACES_AP0.validate().is_ok());
+
+        let duplicate_primaries = Chromaticities { blue: rec709.green, .. rec709 };
+        assert!(duplicate_primaries.validate().is_err());
+
+        let negative_white = Chromaticities { white: Vec2(0.3127, -0.329), .. rec709 };
+        assert!(negative_white.validate().is_err());
+
+        let not_a_number = Chromaticities { red: Vec2(f32::NAN, 0.33), .. rec709 };
+        assert!(not_a_number.validate().is_err());
+    }
+
+    #[test]
+    fn preview_pixel_from_top_left(){
+        let rgba: Vec<u8> = (0 .. 3 * 2 * 4).map(|index| index as u8 * 10).collect();
+        let preview = Preview::from_u8_rgba((3, 2), rgba);
+
+        assert_eq!(preview.pixel(Vec2(0, 0)), Some((0, 10, 20, 30)));
+        assert_eq!(preview.pixel(Vec2(2, 0)), Some((80, 90, 100, 110)));
+        assert_eq!(preview.pixel(Vec2(2, 1)), Some((200, 210, 220, 230)));
+
+        assert_eq!(preview.pixel(Vec2(3, 0)), None);
+        assert_eq!(preview.pixel(Vec2(0, 2)), None);
+
+        let truncated = Preview { size: Vec2(3, 2), pixel_data: vec![ 0; 8 ] };
+        assert_eq!(truncated.pixel(Vec2(1, 0)), Some((0, 0, 0, 0)));
+        assert_eq!(truncated.pixel(Vec2(2, 0)), None);
+    }
+
+    #[test]
+    fn preview_u8_rgba_roundtrip(){
+        let rgba = vec![0xFF, 0x00, 0x80, 0x7F, 12, 200, 255, 1];
+        let preview = Preview::from_u8_rgba((2, 1), rgba.clone());
+        assert_eq!(&preview.pixel_data[.. 4], &[-1, 0, -128, 127]);
+
+        let mut bytes = Vec::new();
+        preview.write(&mut bytes).unwrap();
+        assert_eq!(&bytes[2 * u32::BYTE_SIZE ..], rgba.as_slice());
+
+        let read = Preview::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.to_u8_rgba(), rgba);
+        assert_eq!(read.to_u8_rgba()[0], 255);
+    }
+
+    #[test]
+    fn reject_absurd_channel_count(){
+        let channels = (0 ..= ChannelList::MAX_CHANNEL_COUNT)
+            .map(|index| ChannelDescription::named(Text::new_or_panic(format!("{:06}", index)), SampleType::F16))
+            .collect();
+
+        let channels = ChannelList::new(channels);
+        let data_window = IntegerBounds::new((0, 0), (4, 4));
+
+        match channels.validate(false, data_window, false) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "too many channels"),
+            other => panic!("absurd channel count not rejected: {:?}", other),
+        }
+
+        let mut bytes = Vec::new();
+        channels.write(&mut bytes).unwrap();
+        let read = ChannelList::read(&mut PeekRead::new(bytes.as_slice())).unwrap();
+        assert!(read.validate(false, data_window, false).is_err());
+
+        let few_channels = ChannelList::new(channels.list[.. 3].iter().cloned().collect());
+        assert!(few_channels.validate(false, data_window, true).is_ok());
+    }
+
+    #[test]
+    fn reject_unknown_attribute_exceeding_file(){
+        let mut bytes = Vec::new();
+        Text::from("rabbit").write_null_terminated(&mut bytes).unwrap();
+        Text::from("carrot").write_null_terminated(&mut bytes).unwrap();
+        i32::MAX.write(&mut bytes).unwrap();
+        u8::write_slice(&mut bytes, &[7; 300]).unwrap();
+
+        match super::read(&mut PeekRead::new(bytes.as_slice()), 300) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "attribute size exceeds file"),
+            other => panic!("gigantic attribute size not rejected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn type_name_length_depends_on_long_names(){
+        let attribute_bytes = |type_name_length: usize| {
+            let mut bytes = Vec::new();
+            Text::from("rabbit").write_null_terminated(&mut bytes).unwrap();
+            Text::from_slice_unchecked(&vec![ b'c'; type_name_length ]).write_null_terminated(&mut bytes).unwrap();
+            0_i32.write(&mut bytes).unwrap();
+            bytes
+        };
+
+        let short_names = 32;
+        let long_names = 256;
+
+        assert!(super::read(&mut PeekRead::new(attribute_bytes(31).as_slice()), short_names).is_ok());
+        assert!(super::read(&mut PeekRead::new(attribute_bytes(40).as_slice()), long_names).is_ok());
+
+        for &length in &[ 33, 40 ] {
+            match super::read(&mut PeekRead::new(attribute_bytes(length).as_slice()), short_names) {
+                Err(Error::Invalid(message)) => assert_eq!(message, "attribute type name too long"),
+                other => panic!("type name of {} bytes not rejected: {:?}", length, other),
+            }
+
+            let bytes = attribute_bytes(length);
+            match LazyAttribute::read(&mut PeekRead::new(Tracking::new(bytes.as_slice())), short_names) {
+                Err(Error::Invalid(message)) => assert_eq!(message, "attribute type name too long"),
+                other => panic!("lazy type name of {} bytes not rejected: {:?}", length, other),
+            }
+        }
+    }
+
+    #[test]
+    fn window_with_negative_origin(){
+        let mut bytes = Vec::new();
+        i32::write_slice(&mut bytes, &[ -3, -2, 4, 5 ]).unwrap(); // x min, y min, x max, y max
+
+        let bounds = IntegerBounds::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(bounds.origin(), Vec2(-3, -2));
+        assert_eq!(bounds.size, Vec2(8, 8), "maximum coordinates are inclusive");
+        assert_eq!(bounds.max(), Vec2(4, 5));
+
+        let header = crate::meta::header::Header::new(
+            Text::from("layer"), bounds.size,
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        ).with_position(bounds.origin());
+
+        assert_eq!(header.data_window_origin(), Vec2(-3, -2));
+        assert_eq!(header.data_window(), bounds);
+    }
+
+    #[test]
+    fn count_tiles_in_window(){
+        let tiles = TileDescription {
+            tile_size: Vec2(64, 64),
+            level_mode: LevelMode::Singular,
+            rounding_mode: RoundingMode::Down,
+        };
+
+        // the maximum coordinates are inclusive, so this window is 100 pixels wide and high
+        let window = IntegerBounds::new(Vec2(-50, -50), Vec2(100, 100));
+        assert_eq!(window.max(), Vec2(49, 49));
+        assert_eq!(tiles.tiles_in_window(window), Vec2(2, 2));
+
+        assert_eq!(tiles.tiles_in_window(IntegerBounds::from_dimensions((128, 64))), Vec2(2, 1));
+        assert_eq!(tiles.tiles_in_window(IntegerBounds::from_dimensions((129, 1))), Vec2(3, 1));
+        assert_eq!(tiles.tiles_in_window(IntegerBounds::zero()), Vec2(0, 0));
+    }
+
+    #[test]
+    fn lazy_attributes_skip_channel_list(){
+        let header = crate::meta::header::Header::new(
+            Text::from("layer"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        let mut bytes = Vec::new();
+        header.write(&mut bytes).unwrap();
+
+        let attributes = LazyAttribute::read_header(&mut PeekRead::new(Tracking::new(bytes.as_slice())), 256).unwrap();
+        let find = |name: &str| attributes.iter().find(|attribute| attribute.name == *name).unwrap();
+
+        // corrupt the sample type of the first channel, which follows the null-terminated channel name
+        let channels = find("channels");
+        bytes[channels.value_byte_position + 2] = 42;
+
+        // locating the compression does not require parsing the now invalid channel list
+        let attributes = LazyAttribute::read_header(&mut PeekRead::new(Tracking::new(bytes.as_slice())), 256).unwrap();
+        let compression = attributes.iter().find(|attribute| attribute.name == *"compression").unwrap();
+
+        let mut read = Cursor::new(bytes.as_slice());
+        assert_eq!(compression.value(&mut read).unwrap(), AttributeValue::Compression(header.compression));
+        assert!(find("channels").value(&mut read).is_err());
+    }
+
+    #[test]
+    fn non_positive_channel_sampling_is_rejected(){
+        let data_window = IntegerBounds::from_dimensions((4, 4));
+
+        for &sampling in &[Vec2(0, 1), Vec2(1, 0), Vec2(0, 0)] {
+            let channel = ChannelDescription { sampling, .. ChannelDescription::named("Y", SampleType::F16) };
+
+            // reading does not check the sampling, only validation does
+            let mut bytes = Vec::new();
+            channel.write(&mut bytes).unwrap();
+            let read_channel = ChannelDescription::read(&mut PeekRead::new(bytes.as_slice())).unwrap();
+
+            for &strict in &[false, true] {
+                match read_channel.validate(true, data_window, strict) {
+                    Err(Error::Invalid(message)) => assert_eq!(message, "zero sampling factor"),
+                    other => panic!("zero sampling not rejected: {:?}", other),
+                }
+            }
+        }
+
+        // negative sampling cannot be represented and is rejected while reading
+        let mut bytes = Vec::new();
+        ChannelDescription::named("Y", SampleType::F16).write(&mut bytes).unwrap();
+        let sampling_start = bytes.len() - 2 * i32::BYTE_SIZE;
+        bytes.truncate(sampling_start);
+        (-1_i32).write(&mut bytes).unwrap();
+        1_i32.write(&mut bytes).unwrap();
+
+        assert!(ChannelDescription::read(&mut PeekRead::new(bytes.as_slice())).is_err());
+    }
+
+    #[test]
+    fn bytes_per_sample_of_each_sample_type(){
+        assert_eq!(SampleType::F16.bytes_per_sample(), 2);
+        assert_eq!(SampleType::F32.bytes_per_sample(), 4);
+        assert_eq!(SampleType::U32.bytes_per_sample(), 4);
+
+        let channels = ChannelList::new(smallvec![
+            ChannelDescription::named("A", SampleType::F16),
+            ChannelDescription::named("B", SampleType::F32),
+            ChannelDescription::named("C", SampleType::U32),
+        ]);
+
+        assert_eq!(channels.bytes_per_pixel, 2 + 4 + 4);
+    }
+
+    #[test]
+    fn invalid_sample_type_is_rejected_by_all_readers(){
+        let mut channel_bytes = Vec::new();
+        ChannelDescription::named("R", SampleType::F16).write(&mut channel_bytes).unwrap();
+        sequence_end::write(&mut channel_bytes).unwrap();
+
+        // overwrite the pixel type, which directly follows the null-terminated name
+        channel_bytes[2 .. 6].copy_from_slice(&3_i32.to_le_bytes());
+
+        let expected_message = match SampleType::read(&mut &channel_bytes[2..]) {
+            Err(Error::Invalid(message)) => message,
+            other => panic!("sample type 3 should be invalid, but was {:?}", other),
+        };
+
+        match ChannelDescription::read(&mut channel_bytes.as_slice()) {
+            Err(Error::Invalid(message)) => assert_eq!(message, expected_message),
+            other => panic!("channel with sample type 3 should be invalid, but was {:?}", other),
+        }
+
+        let attribute = AttributeValue::read(
+            &mut PeekRead::new(Cursor::new(&channel_bytes)),
+            Text::from_slice_unchecked(type_names::CHANNEL_LIST), channel_bytes.len()
+        ).unwrap();
+
+        match attribute {
+            Err(Error::Invalid(message)) => assert_eq!(message, expected_message),
+            other => panic!("channel list with sample type 3 should be invalid, but was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn registry_parses_custom_type(){
+        let affine: [f32; 6] = [ 1.0, 0.5, 3.0, 0.0, 2.0, -4.0 ];
+        let mut bytes = Vec::new();
+        f32::write_slice(&mut bytes, &affine).unwrap();
+
+        let read_with = |registry: &AttributeTypeRegistry| AttributeValue::read_with_registry(
+            &mut PeekRead::new(bytes.as_slice()), Text::from("float3x2"), bytes.len(), registry
+        ).unwrap().unwrap();
+
+        let registry = AttributeTypeRegistry::default();
+        assert!(registry.contains(type_names::F32MATRIX3X3));
+        assert_eq!(read_with(&registry), AttributeValue::Custom { kind: Text::from("float3x2"), bytes: bytes.clone() });
+
+        // embed the affine 2d transformation into a homogeneous 3x3 matrix
+        let registry = registry.with_type("float3x2", |bytes: &[u8]| {
+            let mut matrix = [0.0_f32; 9];
+            f32::read_slice(&mut &bytes[..], &mut matrix[.. 6])?;
+            matrix[8] = 1.0;
+            Ok(AttributeValue::Matrix3x3(matrix))
+        });
+
+        let expected = AttributeValue::Matrix3x3([ 1.0, 0.5, 3.0, 0.0, 2.0, -4.0, 0.0, 0.0, 1.0 ]);
+        assert_eq!(read_with(&registry), expected);
+
+        let custom = AttributeValue::Custom { kind: Text::from("float3x2"), bytes: bytes.clone() };
+        assert_eq!(registry.resolve(custom).unwrap(), expected);
+
+        // built-in types are still parsed by the registry
+        let mut int_bytes = Vec::new();
+        AttributeValue::I32(7).write(&mut int_bytes).unwrap();
+        let parsed = registry.parse(Text::from_slice_unchecked(type_names::I32), int_bytes.clone()).unwrap();
+        assert_eq!(parsed, AttributeValue::I32(7));
+
+        let parsed = AttributeTypeRegistry::empty().parse(Text::from("int"), int_bytes.clone()).unwrap();
+        assert_eq!(parsed, AttributeValue::Custom { kind: Text::from("int"), bytes: int_bytes });
+    }
+
+    #[test]
+    fn group_channels_by_layer(){
+        let channels = ChannelList::new(smallvec![
+            ChannelDescription::named("Z", SampleType::F32),
+            ChannelDescription::named("diffuse.G", SampleType::F16),
+            ChannelDescription::named("diffuse.R", SampleType::F16),
+            ChannelDescription::named("light.key.R", SampleType::F16),
+        ]);
+
+        let groups = channels.grouped_by_layer();
+        assert_eq!(groups.len(), 3);
+
+        let names = |layer: &str| groups[&Text::from(layer)].iter()
+            .map(|channel| channel.name.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(names(""), vec!["Z"]);
+        assert_eq!(names("diffuse"), vec!["diffuse.G", "diffuse.R"]);
+        assert_eq!(names("light.key"), vec!["light.key.R"]);
+    }
+
     #[test]
     fn time_code_pack(){
         let mut rng = thread_rng();
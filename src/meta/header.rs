@@ -3,6 +3,7 @@
 //! Defines some data types that list all standard attributes.
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use crate::meta::attribute::*; // FIXME shouldn't this need some more imports????
 use crate::meta::*;
 use crate::math::Vec2;
@@ -78,6 +79,12 @@ pub struct Header {
     pub own_attributes: LayerAttributes,
 }
 
+/// A header read from a file, which may describe a part with an unknown type.
+enum ReadPart {
+    Known(Header),
+    Unknown(UnknownPart),
+}
+
 /// Includes mandatory fields like pixel aspect or display window
 /// which must be the same for all layers.
 /// For more attributes, see struct `LayerAttributes`.
@@ -158,7 +165,7 @@ pub struct LayerAttributes {
     pub comments: Option<Text>,
 
     /// The date of image creation, in `YYYY:MM:DD hh:mm:ss` format.
-    // TODO parse!
+    /// Use `CaptureDate::parse` to extract the components.
     pub capture_date: Option<Text>,
 
     /// Time offset from UTC.
@@ -241,6 +248,21 @@ pub struct LayerAttributes {
     /// Does not contain the attributes already present in the `Header` or `LayerAttributes` struct.
     /// Does not contain attributes that are standardized to be the same for all layers: no chromaticities and no time codes.
     pub other: HashMap<Text, AttributeValue>,
+
+    /// The order in which the attributes of this header appeared in the file it was read from.
+    /// Empty for layers that were not read from a file.
+    pub attribute_order: AttributeOrder,
+}
+
+/// The names of all attributes of a header, in the order they appeared in a file.
+/// When writing, attributes listed here are written in this order,
+/// followed by any other attributes in the default order.
+/// As this only affects the layout of the file, it is ignored when comparing attributes.
+#[derive(Clone, Debug, Default)]
+pub struct AttributeOrder(pub Vec<Text>);
+
+impl PartialEq for AttributeOrder {
+    fn eq(&self, _: &Self) -> bool { true }
 }
 
 
@@ -259,6 +281,26 @@ impl LayerAttributes {
         Self { layer_position: data_position, ..self }
     }
 
+    /// Set the `owner` attribute of this layer.
+    pub fn with_owner(self, owner: impl Into<Text>) -> Self {
+        Self { owner: Some(owner.into()), ..self }
+    }
+
+    /// Set the `comments` attribute of this layer.
+    pub fn with_comments(self, comments: impl Into<Text>) -> Self {
+        Self { comments: Some(comments.into()), ..self }
+    }
+
+    /// Set the `capDate` attribute of this layer.
+    pub fn with_capture_date(self, capture_date: CaptureDate) -> Self {
+        Self { capture_date: Some(capture_date.to_text()), ..self }
+    }
+
+    /// Set the `wrapmodes` attribute of this layer.
+    pub fn with_wrap_modes(self, horizontal: WrapMode, vertical: WrapMode) -> Self {
+        Self { wrap_mode_name: Some(WrapMode::pair_to_text(horizontal, vertical)), ..self }
+    }
+
     /// Set all common camera projection attributes at once.
     pub fn with_camera_frustum(
         self,
@@ -570,10 +612,17 @@ impl Header {
 
     /// Maximum byte length of an uncompressed or compressed block, used for validation.
     pub fn max_block_byte_size(&self) -> usize {
-        self.channels.bytes_per_pixel * match self.blocks {
+        let max_pixel_bytes =
+            if self.deep {
+                // a deep pixel may contain multiple samples, and the offset table stores an i32 per pixel
+                let max_samples = self.max_samples_per_pixel.unwrap_or(1).max(1);
+                (self.channels.bytes_per_pixel * max_samples).max(i32::BYTE_SIZE)
+            }
+            else { self.channels.bytes_per_pixel };
+
+        max_pixel_bytes * match self.blocks {
             BlockDescription::Tiles(tiles) => tiles.tile_size.area(),
             BlockDescription::ScanLines => self.compression.scan_lines_per_block() * self.layer_size.width()
-            // TODO What about deep data???
         }
     }
 
@@ -631,11 +680,11 @@ impl Header {
                 return Err(Error::invalid("unspecified line order in scan line images"));
             }
 
-            if self.layer_size == Vec2(0, 0) {
+            if self.layer_size.area() == 0 {
                 return Err(Error::invalid("empty data window"));
             }
 
-            if self.shared_attributes.display_window.size == Vec2(0,0) {
+            if self.shared_attributes.display_window.size.area() == 0 {
                 return Err(Error::invalid("empty display window"));
             }
 
@@ -659,6 +708,11 @@ impl Header {
             attribute::validate(name, value, long_names, allow_subsampling, self.data_window(), strict)?;
         }
 
+        // validate before computing the chunk count, which divides by the tile size
+        if let BlockDescription::Tiles(tiles) = self.blocks {
+            tiles.validate()?;
+        }
+
         // this is only to check whether someone tampered with our precious values, to avoid writing an invalid file
         if self.chunk_count != compute_chunk_count(self.compression, self.layer_size, self.blocks) {
             return Err(Error::invalid("chunk count attribute")); // TODO this may be an expensive check?
@@ -710,17 +764,40 @@ impl Header {
 
     /// Read the headers without validating them.
     pub fn read_all(read: &mut PeekRead<impl Read>, version: &Requirements, pedantic: bool) -> Result<Headers> {
+        Self::read_all_with_registry(read, version, pedantic, None)
+    }
+
+    /// Read the headers without validating them, parsing the attribute values with the registry if one is specified.
+    pub(crate) fn read_all_with_registry(
+        read: &mut PeekRead<impl Read>, version: &Requirements, pedantic: bool, registry: Option<&AttributeTypeRegistry>
+    ) -> Result<Headers> {
+        Self::read_all_parts(read, version, pedantic, registry, false).map(|(headers, _)| headers)
+    }
+
+    /// Read the headers without validating them, parsing the attribute values with the registry if one is specified.
+    /// If desired, parts of multi-part files with an unknown type are returned separately instead of failing.
+    pub(crate) fn read_all_parts(
+        read: &mut PeekRead<impl Read>, version: &Requirements, pedantic: bool,
+        registry: Option<&AttributeTypeRegistry>, skip_unknown_parts: bool
+    ) -> Result<(Headers, Vec<UnknownPart>)> {
         if !version.is_multilayer() {
-            Ok(smallvec![ Header::read(read, version, pedantic)? ])
+            Ok((smallvec![ Header::read_with_registry(read, version, pedantic, registry)? ], Vec::new()))
         }
         else {
             let mut headers = SmallVec::new();
+            let mut unknown_parts = Vec::new();
 
+            // only chunks of multi-part files contain the part number, which is required to skip the chunks of a part
             while !sequence_end::has_come(read)? {
-                headers.push(Header::read(read, version, pedantic)?);
+                match Header::read_part(read, version, pedantic, registry, skip_unknown_parts)? {
+                    ReadPart::Known(header) => headers.push(header),
+                    ReadPart::Unknown(part) => unknown_parts.push(UnknownPart {
+                        index: headers.len() + unknown_parts.len(), .. part
+                    }),
+                }
             }
 
-            Ok(headers)
+            Ok((headers, unknown_parts))
         }
     }
 
@@ -738,7 +815,12 @@ impl Header {
     }
 
     /// Iterate over all `(name, attribute_value)` pairs in this header that would be written to a file.
-    /// The order of attributes is arbitrary and may change in future versions.
+    /// If the header was read from a file, the attributes come in the order of that file,
+    /// as recorded in `own_attributes.attribute_order`.
+    /// Any other attributes follow in the default order:
+    /// Required attributes come first, followed by the optional standard attributes,
+    /// followed by the custom attributes sorted by name.
+    /// The default order may change in future versions, but never varies between runs.
     /// Will always contain all strictly required attributes, such as channels, compression, data window, and similar.
     /// Hint: Use `attribute.kind_name()` to obtain the standardized name of the attribute type.
     /// Does not validate the header or attributes.
@@ -796,12 +878,12 @@ impl Header {
 
 
         let block_type_and_tiles = expect_is_iter(once_with(move ||{
-            let (block_type, tiles) = match self.blocks {
-                BlockDescription::ScanLines => (attribute::BlockType::ScanLine, None),
-                BlockDescription::Tiles(tiles) => (attribute::BlockType::Tile, Some(tiles))
+            let tiles = match self.blocks {
+                BlockDescription::ScanLines => None,
+                BlockDescription::Tiles(tiles) => Some(tiles),
             };
 
-            once((BLOCK_TYPE, BlockType(block_type)))
+            once((BLOCK_TYPE, BlockType(self.block_type())))
                 .chain(tiles.map(|tiles| (TILES, TileDescription(tiles))))
         }).flatten());
 
@@ -822,10 +904,20 @@ impl Header {
             }).flatten()
         );
 
+        // deep layers require this attribute, where `-1` denotes an unknown sample count
+        let max_samples = expect_is_iter(
+            once_with(move ||{
+                match self.max_samples_per_pixel {
+                    Some(max_samples) => Some((MAX_SAMPLES, usize_as_i32(max_samples))),
+                    None if self.deep => Some((MAX_SAMPLES, I32(-1))),
+                    None => None,
+                }
+            }).flatten()
+        );
+
         let opt_core_attrs = optional_attributes!(
-            DEEP_DATA_VERSION: I32 = &self.deep_data_version,
-            MAX_SAMPLES: usize_as_i32 = &self.max_samples_per_pixel
-        ).chain(block_type_and_tiles).chain(dwa_compr_level);
+            DEEP_DATA_VERSION: I32 = &self.deep_data_version
+        ).chain(max_samples).chain(block_type_and_tiles).chain(dwa_compr_level);
 
         let req_core_attrs = required_attributes!(
             // chunks is not actually required, but always computed in this library anyways
@@ -880,23 +972,59 @@ impl Header {
             SOFTWARE: Text = &self.own_attributes.software_name
         );
 
-        let other = self.own_attributes.other.iter()
+        // sort the custom attributes, as the iteration order of hash maps varies between runs
+        let mut other: Vec<(&TextSlice, AttributeValue)> = self.own_attributes.other.iter()
             .chain(self.shared_attributes.other.iter())
-            .map(|(name, val)| (name.as_slice(), val.clone())); // TODO no clone
+            .map(|(name, val)| (name.as_slice(), val.clone())) // TODO no clone
+            .collect();
 
-        req_core_attrs
+        other.sort_by(|(name, _), (other_name, _)| name.cmp(other_name));
+
+        let mut attributes: Vec<(&TextSlice, AttributeValue)> = req_core_attrs
             .chain(opt_core_attrs)
             .chain(opt_attr)
             .chain(other)
+            .collect();
+
+        // replay the order of the original file, keeping the default order for all remaining attributes
+        let order = &self.own_attributes.attribute_order.0;
+        if !order.is_empty() {
+            attributes.sort_by_key(|(name, _)|
+                order.iter().position(|ordered| ordered.as_slice() == *name).unwrap_or(order.len())
+            );
+        }
+
+        attributes.into_iter()
     }
 
     /// Read the value without validating.
     pub fn read(read: &mut PeekRead<impl Read>, requirements: &Requirements, pedantic: bool) -> Result<Self> {
+        Self::read_with_registry(read, requirements, pedantic, None)
+    }
+
+    /// Read the value without validating, parsing the attribute values with the registry if one is specified.
+    pub(crate) fn read_with_registry(
+        read: &mut PeekRead<impl Read>, requirements: &Requirements, pedantic: bool, registry: Option<&AttributeTypeRegistry>
+    ) -> Result<Self> {
+        match Self::read_part(read, requirements, pedantic, registry, false)? {
+            ReadPart::Known(header) => Ok(header),
+            ReadPart::Unknown(_) => unreachable!("unknown parts are only returned when skipping them"),
+        }
+    }
+
+    /// Read the value without validating, parsing the attribute values with the registry if one is specified.
+    /// If desired, a part with an unknown type is returned as such, instead of failing.
+    /// Its index is not yet known and must be set by the caller.
+    fn read_part(
+        read: &mut PeekRead<impl Read>, requirements: &Requirements, pedantic: bool,
+        registry: Option<&AttributeTypeRegistry>, skip_unknown_type: bool
+    ) -> Result<ReadPart> {
         let max_string_len = if requirements.has_long_names { 256 } else { 32 }; // TODO DRY this information
 
         // these required attributes will be filled when encountered while parsing
         let mut tiles = None;
         let mut block_type = None;
+        let mut unknown_block_type = None;
         let mut version = None;
         let mut chunk_count = None;
         let mut max_samples_per_pixel = None;
@@ -906,13 +1034,34 @@ impl Header {
         let mut display_window = None;
         let mut line_order = None;
         let mut dwa_compression_level = None;
+        let mut pixel_aspect = None;
+        let mut screen_window_center = None;
+        let mut screen_window_width = None;
 
         let mut layer_attributes = LayerAttributes::default();
         let mut image_attributes = ImageAttributes::new(IntegerBounds::zero());
+        let mut required_attribute_names: SmallVec<[&'static [u8]; 14]> = SmallVec::new();
+        let mut attribute_order = Vec::new();
 
         // read each attribute in this header
         while !sequence_end::has_come(read)? {
-            let (attribute_name, value) = attribute::read(read, max_string_len)?;
+            let (attribute_name, value) = attribute::read_with_registry(read, max_string_len, registry)?;
+
+            // a required attribute may appear only once.
+            // if it appears again, keep the first one, unless strict reading is desired
+            let required_name = standard_names::REQUIRED.iter()
+                .find(|&&name| name == attribute_name.as_slice());
+
+            if let Some(&required_name) = required_name {
+                if required_attribute_names.contains(&required_name) {
+                    if pedantic { return Err(Error::invalid("duplicate required attribute")); }
+                    else { continue; }
+                }
+
+                required_attribute_names.push(required_name);
+            }
+
+            attribute_order.push(attribute_name.clone());
 
             // if the attribute value itself is ok, record it
             match value {
@@ -925,7 +1074,12 @@ impl Header {
 
                     // the following attributes will only be set if the type matches the commonly used type for that attribute
                     match (attribute_name.as_slice(), value) {
-                        (name::BLOCK_TYPE, Text(value)) => block_type = Some(attribute::BlockType::parse(value)?),
+                        (name::BLOCK_TYPE, Text(value)) => match attribute::BlockType::from_text_bytes(value.as_slice()) {
+                            Some(parsed) => block_type = Some(parsed),
+                            None if skip_unknown_type => unknown_block_type = Some(value),
+                            None => return Err(Error::invalid("block type attribute value")),
+                        },
+
                         (name::TILES, TileDescription(value)) => tiles = Some(value),
                         (name::CHANNELS, ChannelList(value)) => channels = Some(value),
                         (name::COMPRESSION, Compression(value)) => compression = Some(value),
@@ -934,17 +1088,19 @@ impl Header {
                         (name::LINE_ORDER, LineOrder(value)) => line_order = Some(value),
                         (name::DEEP_DATA_VERSION, I32(value)) => version = Some(value),
 
+                        // `-1` denotes an unknown sample count
+                        (name::MAX_SAMPLES, I32(-1)) => max_samples_per_pixel = None,
                         (name::MAX_SAMPLES, I32(value)) => max_samples_per_pixel = Some(
                             i32_to_usize(value, "max sample count")?
                         ),
 
                         (name::CHUNKS, I32(value)) => chunk_count = Some(
-                            i32_to_usize(value, "chunk count")?
+                            i32_to_usize(value, "negative chunk count")?
                         ),
 
                         (name::NAME, Text(value)) => layer_attributes.layer_name = Some(value),
-                        (name::WINDOW_CENTER, FloatVec2(value)) => layer_attributes.screen_window_center = value,
-                        (name::WINDOW_WIDTH, F32(value)) => layer_attributes.screen_window_width = value,
+                        (name::WINDOW_CENTER, FloatVec2(value)) => screen_window_center = Some(value),
+                        (name::WINDOW_WIDTH, F32(value)) => screen_window_width = Some(value),
 
                         (name::WHITE_LUMINANCE, F32(value)) => layer_attributes.white_luminance = Some(value),
                         (name::ADOPTED_NEUTRAL, FloatVec2(value)) => layer_attributes.adopted_neutral = Some(value),
@@ -982,7 +1138,7 @@ impl Header {
                         (name::FOV_Y, F32(value)) => layer_attributes.vertical_field_of_view = Some(value),
                         (name::SOFTWARE, Text(value)) => layer_attributes.software_name = Some(value),
 
-                        (name::PIXEL_ASPECT, F32(value)) => image_attributes.pixel_aspect = value,
+                        (name::PIXEL_ASPECT, F32(value)) => pixel_aspect = Some(value),
                         (name::TIME_CODE, TimeCode(value)) => image_attributes.time_code = Some(value),
                         (name::CHROMATICITIES, Chromaticities(value)) => image_attributes.chromaticities = Some(value),
 
@@ -1009,6 +1165,16 @@ impl Header {
             }
         }
 
+        // the other attributes of a part with an unknown type cannot be interpreted, except for the chunk count
+        if let Some(part_type) = unknown_block_type {
+            return Ok(ReadPart::Unknown(UnknownPart {
+                index: 0,
+                name: layer_attributes.layer_name,
+                part_type,
+                chunk_count: chunk_count.ok_or(missing_attribute("chunk count"))?,
+            }));
+        }
+
         // construct compression with parameters from properties
         let compression = match (dwa_compression_level, compression) {
             (Some(level), Some(Compression::DWAA(_))) => Some(Compression::DWAA(Some(level))),
@@ -1018,11 +1184,23 @@ impl Header {
         };
 
         let compression = compression.ok_or(missing_attribute("compression"))?;
+
+        // many files omit these required attributes, expecting the default values of the specification
+        if pedantic {
+            if pixel_aspect.is_none() { return Err(missing_attribute("pixel aspect ratio")); }
+            if screen_window_center.is_none() { return Err(missing_attribute("screen window center")); }
+            if screen_window_width.is_none() { return Err(missing_attribute("screen window width")); }
+        }
+
+        image_attributes.pixel_aspect = pixel_aspect.unwrap_or(1.0);
+        layer_attributes.screen_window_center = screen_window_center.unwrap_or(Vec2(0.0, 0.0));
+        layer_attributes.screen_window_width = screen_window_width.unwrap_or(1.0);
         image_attributes.display_window = display_window.ok_or(missing_attribute("display window"))?;
 
         let data_window = data_window.ok_or(missing_attribute("data window"))?;
         data_window.validate(None)?; // validate now to avoid errors when computing the chunk_count
         layer_attributes.layer_position = data_window.position;
+        layer_attributes.attribute_order = AttributeOrder(attribute_order);
 
 
         // validate now to avoid errors when computing the chunk_count
@@ -1063,7 +1241,7 @@ impl Header {
             deep: block_type == Some(BlockType::DeepScanLine) || block_type == Some(BlockType::DeepTile),
         };
 
-        Ok(header)
+        Ok(ReadPart::Known(header))
     }
 
     /// Without validation, write this instance to the byte stream.
@@ -1081,6 +1259,194 @@ impl Header {
     pub fn data_window(&self) -> IntegerBounds {
         IntegerBounds::new(self.own_attributes.layer_position, self.layer_size)
     }
+
+    /// The position of the top left pixel of this layer, which may be negative.
+    /// This is the minimum coordinate of the data window.
+    pub fn data_window_origin(&self) -> Vec2<i32> {
+        self.own_attributes.layer_position
+    }
+
+    /// Convert a pixel position in the global 2D space of the file,
+    /// which may be negative, into the index of that pixel in a zero-based buffer
+    /// that stores the largest resolution level of this layer row by row.
+    /// Returns `None` if the position is outside of the data window.
+    pub fn to_buffer_index(&self, position: Vec2<i32>) -> Option<usize> {
+        let origin = self.own_attributes.layer_position;
+        let x = usize::try_from(i64::from(position.x()) - i64::from(origin.x())).ok()?;
+        let y = usize::try_from(i64::from(position.y()) - i64::from(origin.y())).ok()?;
+
+        if x >= self.layer_size.width() || y >= self.layer_size.height() { return None; }
+        Some(y * self.layer_size.width() + x)
+    }
+
+    /// Convert the index of a pixel in a zero-based buffer,
+    /// storing the largest resolution level of this layer row by row,
+    /// back into the global 2D space of the file. The inverse of `to_buffer_index`.
+    /// Returns `None` if the index is outside of the data window.
+    pub fn from_buffer_index(&self, index: usize) -> Option<Vec2<i32>> {
+        if index >= self.layer_size.area() { return None; }
+
+        let relative = Vec2(index % self.layer_size.width(), index / self.layer_size.width());
+        Some(self.own_attributes.layer_position + relative.to_i32())
+    }
+
+    /// The quantization level of `DWAA` and `DWAB` compressed layers, from the `dwaCompressionLevel` attribute.
+    /// Defaults to `Compression::DWA_DEFAULT_COMPRESSION_LEVEL` if the attribute is missing.
+    /// Returns `None` if this layer does not use DWA compression.
+    pub fn dwa_compression_level(&self) -> Option<f32> {
+        match self.compression {
+            Compression::DWAA(level) | Compression::DWAB(level) =>
+                Some(level.unwrap_or(Compression::DWA_DEFAULT_COMPRESSION_LEVEL)),
+
+            _ => None,
+        }
+    }
+
+    /// The names of the views in this stereo or multi-view layer, from the `multiView` attribute.
+    /// The first view is the default view. Returns `None` if the attribute is missing.
+    pub fn multi_view(&self) -> Option<Vec<String>> {
+        self.own_attributes.multi_view_names.as_ref()
+            .map(|names| names.iter().map(Text::to_string).collect())
+    }
+
+    /// The view the channel with the specified name belongs to, according to the `multiView` attribute.
+    /// Channels without a prefix, such as `R`, belong to the default view, which is the first view.
+    /// Channels such as `left.R` or `diffuse.left.R` belong to the `left` view, if that view is listed.
+    /// Returns `None` if there is no `multiView` attribute, or if the channel does not belong to any view.
+    pub fn view_of_channel(&self, channel_name: &Text) -> Option<String> {
+        let views = self.multi_view()?;
+        let channel_name = channel_name.to_string();
+        let mut components = channel_name.rsplit('.').skip(1);
+
+        match components.next() {
+            None => views.into_iter().next(),
+            Some(view) => views.into_iter().find(|listed| listed == view),
+        }
+    }
+
+    /// The `owner` attribute of this layer, if present.
+    pub fn owner(&self) -> Option<&Text> {
+        self.own_attributes.owner.as_ref()
+    }
+
+    /// The `comments` attribute of this layer, if present.
+    pub fn comments(&self) -> Option<&Text> {
+        self.own_attributes.comments.as_ref()
+    }
+
+    /// The `capDate` attribute of this layer, if present.
+    /// Returns `None` if the attribute is missing or not in `YYYY:MM:DD hh:mm:ss` format.
+    pub fn capture_date(&self) -> Option<CaptureDate> {
+        self.own_attributes.capture_date.as_ref().and_then(CaptureDate::parse)
+    }
+
+    /// The horizontal and vertical wrap mode of this texture, from the `wrapmodes` attribute, if present.
+    /// Returns `None` if the attribute is missing or contains an unknown wrap mode.
+    pub fn wrap_modes(&self) -> Option<(WrapMode, WrapMode)> {
+        self.own_attributes.wrap_mode_name.as_ref().and_then(WrapMode::parse_pair)
+    }
+
+    /// The `framesPerSecond` attribute of this layer as a float, for example `23.976` for `24000/1001`.
+    /// Returns `None` if the attribute is missing or has a zero denominator.
+    pub fn frame_rate(&self) -> Option<f64> {
+        self.own_attributes.frames_per_second
+            .and_then(|rational| RationalParts::new(rational).to_f64())
+    }
+
+    /// The size of the display window, with the width stretched by the pixel aspect ratio.
+    /// This is the size at which the image should appear on a screen with square pixels.
+    pub fn display_size(&self) -> Vec2<f32> {
+        let size = self.shared_attributes.display_window.size;
+        Vec2(size.width() as f32 * self.shared_attributes.pixel_aspect, size.height() as f32)
+    }
+
+    /// The kind of blocks this layer is divided into, as stored in the `type` attribute.
+    pub fn block_type(&self) -> BlockType {
+        BlockType::new(self.deep, self.blocks.has_tiles())
+    }
+
+    /// Look up any attribute of this header by its name, as it would be written to a file.
+    /// This includes required attributes like `dataWindow` or `channels`, as well as custom attributes.
+    /// Returns `None` if this header does not contain an attribute with that name.
+    pub fn attribute(&self, name: &TextSlice) -> Option<AttributeValue> {
+        self.all_named_attributes()
+            .find(|&(attribute_name, _)| attribute_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Look up an `f32` attribute of this header by its name, for example `longitude` or `altitude`.
+    /// Returns `None` if this header does not contain an attribute with that name,
+    /// or if the attribute is not an `f32`.
+    pub fn float_attribute(&self, name: &TextSlice) -> Option<f32> {
+        self.attribute(name).and_then(|value| value.to_f32().ok())
+    }
+
+    /// Look up an `f64` attribute of this header by its name.
+    /// Also returns `f32` attributes, as they can be converted without losing precision.
+    /// Returns `None` if this header does not contain an attribute with that name,
+    /// or if the attribute is neither an `f64` nor an `f32`.
+    pub fn double_attribute(&self, name: &TextSlice) -> Option<f64> {
+        let value = self.attribute(name)?;
+        value.to_f64().or_else(|_| value.to_f32().map(f64::from)).ok()
+    }
+
+    /// Whether this layer satisfies the attribute requirements of an ACES image container (SMPTE ST 2065-4).
+    /// Requires the `acesImageContainerFlag`, the AP0 chromaticities, an adopted neutral,
+    /// no compression, and only `f16` channels.
+    pub fn is_aces_container(&self) -> bool {
+        let has_container_flag = self.own_attributes.other.get(&b"acesImageContainerFlag"[..])
+            .or_else(|| self.shared_attributes.other.get(&b"acesImageContainerFlag"[..]))
+            .map_or(false, |flag| flag == &AttributeValue::I32(1));
+
+        let has_aces_primaries = self.shared_attributes.chromaticities.map_or(false, |chromaticities| {
+            let aces = Chromaticities::ACES_AP0;
+            let close = |a: Vec2<f32>, b: Vec2<f32>| (a.x() - b.x()).abs() < 1.0e-4 && (a.y() - b.y()).abs() < 1.0e-4;
+
+            close(chromaticities.red, aces.red) && close(chromaticities.green, aces.green)
+                && close(chromaticities.blue, aces.blue) && close(chromaticities.white, aces.white)
+        });
+
+        has_container_flag && has_aces_primaries
+            && self.own_attributes.adopted_neutral.is_some()
+            && self.compression == Compression::Uncompressed
+            && self.channels.list.iter().all(|channel| channel.sample_type == SampleType::F16)
+    }
+
+    /// Look up the content of a `string` attribute by its name,
+    /// for example the manifest `cryptomatte/1a2b3c4/manifest` of a cryptomatte layer.
+    /// Searches the custom attributes of this layer and of the image,
+    /// as well as the standard text attributes like `owner` or `comments`.
+    /// Returns `None` if no such attribute exists, if it is not a string,
+    /// or if its bytes are not valid UTF-8.
+    pub fn string_attribute(&self, name: &str) -> Option<&str> {
+        use crate::meta::header::standard_names as std_name;
+
+        let custom = self.own_attributes.other.get(name.as_bytes())
+            .or_else(|| self.shared_attributes.other.get(name.as_bytes()));
+
+        let text = match custom {
+            Some(AttributeValue::Text(text)) => Some(text),
+            Some(_) => None,
+
+            None => {
+                let own = &self.own_attributes;
+                match name.as_bytes() {
+                    std_name::NAME => own.layer_name.as_ref(),
+                    std_name::RENDERING_TRANSFORM => own.rendering_transform_name.as_ref(),
+                    std_name::LOOK_MOD_TRANSFORM => own.look_modification_transform_name.as_ref(),
+                    std_name::OWNER => own.owner.as_ref(),
+                    std_name::COMMENTS => own.comments.as_ref(),
+                    std_name::CAPTURE_DATE => own.capture_date.as_ref(),
+                    std_name::WRAP_MODES => own.wrap_mode_name.as_ref(),
+                    std_name::VIEW => own.view_name.as_ref(),
+                    std_name::SOFTWARE => own.software_name.as_ref(),
+                    _ => None,
+                }
+            }
+        };
+
+        text.and_then(|text| std::str::from_utf8(text.as_slice()).ok())
+    }
 }
 
 
@@ -1153,6 +1519,13 @@ pub mod standard_names {
         FOV_Y: b"fieldOfViewVertical",
         SOFTWARE: b"software"
     }
+
+    /// A list containing the names of all attributes that may appear at most once in a header,
+    /// as they describe the layout of the pixel data.
+    pub const REQUIRED: &'static [&'static [u8]] = &[
+        TILES, NAME, BLOCK_TYPE, DEEP_DATA_VERSION, CHUNKS, MAX_SAMPLES, CHANNELS,
+        COMPRESSION, DATA_WINDOW, DISPLAY_WINDOW, LINE_ORDER, PIXEL_ASPECT, WINDOW_CENTER, WINDOW_WIDTH,
+    ];
 }
 
 
@@ -1195,7 +1568,8 @@ impl Default for LayerAttributes {
             far_clip_plane: None,
             horizontal_field_of_view: None,
             vertical_field_of_view: None,
-            other: Default::default()
+            other: Default::default(),
+            attribute_order: Default::default(),
         }
     }
 }
@@ -0,0 +1,177 @@
+//! Describes where each part of an exr file is located.
+//! Intended for inspecting unusual or corrupt files.
+
+use std::ops::Range;
+use std::io::{Seek, SeekFrom};
+use crate::io::*;
+use crate::error::*;
+use crate::meta::{MetaData, magic_number, sequence_end};
+use crate::meta::attribute::{Text, LazyAttribute};
+use crate::block::chunk::Chunk;
+
+
+/// The byte ranges of all structures in an exr file.
+/// All ranges are counted from the start of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLayout {
+
+    /// The magic number and the version field.
+    pub identification: Range<usize>,
+
+    /// All headers, including their terminating null bytes.
+    pub headers: Range<usize>,
+
+    /// The attributes of each header, in the order they appear in the file.
+    pub attributes: Vec<Vec<AttributeLayout>>,
+
+    /// The offset table of each header.
+    pub offset_tables: Vec<Range<usize>>,
+
+    /// The chunks of each header, in the order of the offset table.
+    pub chunks: Vec<Vec<Range<usize>>>,
+}
+
+/// The byte ranges of a single attribute in an exr file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeLayout {
+
+    /// The name of the attribute.
+    pub name: Text,
+
+    /// The name of the type of the attribute value.
+    pub kind: Text,
+
+    /// The whole attribute, including its name, type name, and size.
+    pub bytes: Range<usize>,
+
+    /// Only the value of the attribute.
+    pub value: Range<usize>,
+}
+
+impl FileLayout {
+
+    /// Read the meta data of the file, and find the byte range of each structure in the file.
+    /// Reads every chunk to find its size, but does not decompress the chunks.
+    /// Seeks to the start of the byte source before reading.
+    /// Does not validate the meta data.
+    pub fn read(read: &mut (impl Read + Seek), pedantic: bool) -> Result<(MetaData, Self)> {
+        read.seek(SeekFrom::Start(0))?;
+        let meta_data = MetaData::read_from(read, pedantic)?;
+
+        // read the headers again, this time remembering the position of every attribute
+        read.seek(SeekFrom::Start(0))?;
+        let mut tracking = PeekRead::new(Tracking::new(&mut *read));
+
+        magic_number::skip_identification_bytes(&mut tracking)?;
+        let identification = 0 .. tracking.byte_position();
+
+        let max_name_size = if meta_data.requirements.has_long_names { 256 } else { 32 };
+        let attributes = meta_data.headers.iter()
+            .map(|_| {
+                let attributes = LazyAttribute::read_header(&mut tracking, max_name_size)?;
+                Ok(attributes.into_iter().map(AttributeLayout::from).collect())
+            })
+            .collect::<Result<Vec<Vec<AttributeLayout>>>>()?;
+
+        if meta_data.requirements.is_multilayer() && !sequence_end::has_come(&mut tracking)? {
+            return Err(Error::invalid("header sequence end"));
+        }
+
+        let headers = identification.end .. tracking.byte_position();
+
+        let mut offset_tables = Vec::with_capacity(meta_data.headers.len());
+        let mut table_start = headers.end;
+
+        for header in &meta_data.headers {
+            let table_end = table_start + header.chunk_count * u64::BYTE_SIZE;
+            offset_tables.push(table_start .. table_end);
+            table_start = table_end;
+        }
+
+        let tables = MetaData::read_offset_tables(&mut tracking, &meta_data.headers)?;
+        drop(tracking);
+
+        let chunks = tables.iter()
+            .map(|table| table.iter().map(|&offset| {
+                let start = u64_to_usize_checked(offset, "offset table")?;
+                read.seek(SeekFrom::Start(offset))?;
+
+                let mut tracking = Tracking::new(&mut *read);
+                Chunk::read(&mut tracking, &meta_data)?;
+                Ok(start .. start + tracking.byte_position())
+            }).collect())
+            .collect::<Result<Vec<Vec<Range<usize>>>>>()?;
+
+        Ok((meta_data, FileLayout { identification, headers, attributes, offset_tables, chunks }))
+    }
+}
+
+impl From<LazyAttribute> for AttributeLayout {
+    fn from(attribute: LazyAttribute) -> Self {
+        let value = attribute.value_byte_position .. attribute.value_byte_position + attribute.byte_size;
+
+        let start = attribute.value_byte_position
+            - i32::BYTE_SIZE
+            - attribute.kind.null_terminated_byte_size()
+            - attribute.name.null_terminated_byte_size();
+
+        AttributeLayout { name: attribute.name, kind: attribute.kind, bytes: start .. value.end, value }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn attribute_ranges_cover_the_headers() {
+        let size = Vec2(8, 40);
+        let layer = |name: &str| Layer::new(
+            size, LayerAttributes::named(name), Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(smallvec![ AnyChannel::new("Y", FlatSamples::F32(vec![ 0.5; size.area() ])) ])
+        );
+
+        let image = Image::from_layers(ImageAttributes::with_size(size), smallvec![ layer("first"), layer("second") ]);
+        let mut bytes = Vec::new();
+        image.write().to_buffered(Cursor::new(&mut bytes)).unwrap();
+
+        let (meta_data, layout) = FileLayout::read(&mut Cursor::new(&bytes), true).unwrap();
+        assert_eq!(layout.identification, 0 .. 8);
+        assert_eq!(layout.attributes.len(), 2);
+
+        // attributes follow each other, and each header is terminated by a null byte
+        let mut position = layout.identification.end;
+        for header_attributes in &layout.attributes {
+            for attribute in header_attributes {
+                assert_eq!(attribute.bytes.start, position, "gap before attribute {}", attribute.name);
+                assert_eq!(attribute.bytes.end, attribute.value.end);
+                position = attribute.bytes.end;
+            }
+
+            assert_eq!(bytes[position], 0);
+            position += 1;
+        }
+
+        // multi-layer files terminate the header list with another null byte
+        assert_eq!(bytes[position], 0);
+        assert_eq!(layout.headers, layout.identification.end .. position + 1);
+
+        let name = &layout.attributes[1].iter().find(|attribute| attribute.name == Text::from("name")).unwrap();
+        assert_eq!(&bytes[name.value.clone()], b"second");
+
+        assert_eq!(layout.offset_tables[0].start, layout.headers.end);
+        assert_eq!(layout.offset_tables[1].start, layout.offset_tables[0].end);
+
+        // the chunks follow directly behind the offset tables, and the last chunk ends the file
+        let mut chunks: Vec<Range<usize>> = layout.chunks.iter().flatten().cloned().collect();
+        assert_eq!(chunks.len(), meta_data.headers.iter().map(|header| header.chunk_count).sum::<usize>());
+
+        chunks.sort_by_key(|chunk| chunk.start);
+        assert_eq!(chunks[0].start, layout.offset_tables[1].end);
+        assert!(chunks.windows(2).all(|pair| pair[0].end == pair[1].start));
+        assert_eq!(chunks.last().unwrap().end, bytes.len());
+    }
+}
@@ -5,6 +5,7 @@
 
 pub mod attribute;
 pub mod header;
+pub mod layout;
 
 
 use crate::io::*;
@@ -40,6 +41,25 @@ pub struct MetaData {
 }
 
 
+/// A part of a multi-part file with a type that is not supported by this library,
+/// such as a part type defined by a future version of the specification.
+/// Such parts are only skipped if desired, otherwise reading the file fails.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownPart {
+
+    /// The index of this part in the file, counting all parts, including the skipped ones.
+    pub index: usize,
+
+    /// The name of this part, if it has one.
+    pub name: Option<Text>,
+
+    /// The unknown value of the `type` attribute of this part.
+    pub part_type: Text,
+
+    /// The number of chunks of this part, as specified by its `chunkCount` attribute.
+    pub chunk_count: usize,
+}
+
 /// List of `Header`s.
 pub type Headers = SmallVec<[Header; 3]>;
 
@@ -183,6 +203,23 @@ pub mod magic_number {
             Err(Error::invalid("file identifier missing"))
         }
     }
+
+    /// Consumes the magic number and the version field, and returns the file requirements.
+    /// As other files may coincidentally start with the magic number,
+    /// the version field must also describe a supported exr file.
+    pub fn skip_identification_bytes(read: &mut impl Read) -> Result<Requirements> {
+        self::validate_exr(read)?;
+
+        // unknown feature flags most likely mean that this is not an exr file at all
+        let requirements = Requirements::read(read).map_err(|error| match error {
+            Error::NotSupported(_) => Error::invalid("file identifier missing"),
+            error => error,
+        })?;
+
+        // do this check now in order to fast-fail for newer versions and features than version 2
+        requirements.validate()?;
+        Ok(requirements)
+    }
 }
 
 /// A `0_u8` at the end of a sequence.
@@ -313,18 +350,19 @@ pub fn mip_map_indices(round: RoundingMode, max_resolution: Vec2<usize>) -> impl
 // using the dataWindow and tileDesc attributes and the compression format
 pub fn compute_chunk_count(compression: Compression, data_size: Vec2<usize>, blocks: BlockDescription) -> usize {
 
-    if let BlockDescription::Tiles(tiles) = blocks {
+    // an empty layer has no chunks, and also no resolution levels
+    if data_size.area() == 0 {
+        0
+    }
+
+    else if let BlockDescription::Tiles(tiles) = blocks {
         let round = tiles.rounding_mode;
         let Vec2(tile_width, tile_height) = tiles.tile_size;
 
         // TODO cache all these level values??
         use crate::meta::attribute::LevelMode::*;
         match tiles.level_mode {
-            Singular => {
-                let tiles_x = compute_block_count(data_size.width(), tile_width);
-                let tiles_y = compute_block_count(data_size.height(), tile_height);
-                tiles_x * tiles_y
-            }
+            Singular => tiles.tiles_in_window(IntegerBounds::from_dimensions(data_size)).area(),
 
             MipMap => {
                 mip_map_levels(round, data_size).map(|(_, Vec2(level_width, level_height))| {
@@ -374,33 +412,84 @@ impl MetaData {
     #[must_use]
     pub fn read_from_buffered(buffered: impl Read, pedantic: bool) -> Result<Self> {
         let mut read = PeekRead::new(buffered);
-        MetaData::read_unvalidated_from_buffered_peekable(&mut read, pedantic)
+        MetaData::read_unvalidated_from_buffered_peekable(&mut read, pedantic, None)
     }
 
-    /// Does __not validate__ the meta data completely.
+    /// Read the exr meta data from a reader, parsing all attribute values with the functions of the registry.
+    /// Attributes with types that are not contained in the registry are read as `AttributeValue::Custom`.
+    /// Does not validate the meta data.
     #[must_use]
-    pub(crate) fn read_unvalidated_from_buffered_peekable(read: &mut PeekRead<impl Read>, pedantic: bool) -> Result<Self> {
-        magic_number::validate_exr(read)?;
+    pub fn read_from_buffered_with_registry(buffered: impl Read, pedantic: bool, registry: &AttributeTypeRegistry) -> Result<Self> {
+        let mut read = PeekRead::new(buffered);
+        MetaData::read_unvalidated_from_buffered_peekable(&mut read, pedantic, Some(registry))
+    }
 
-        let requirements = Requirements::read(read)?;
+    /// Read the exr meta data from a borrowed reader, without buffering it.
+    /// Afterwards, the reader is positioned at the first byte of the offset tables,
+    /// so that the offset tables and chunks can be read from the same reader.
+    /// Does not validate the meta data.
+    #[must_use]
+    pub fn read_from(read: &mut impl Read, pedantic: bool) -> Result<Self> {
+        // reading the headers always consumes the byte that was peeked last, which is the terminating null byte
+        let mut read = PeekRead::new(read);
+        MetaData::read_unvalidated_from_buffered_peekable(&mut read, pedantic, None)
+    }
 
-        // do this check now in order to fast-fail for newer versions and features than version 2
-        requirements.validate()?;
+    /// Does __not validate__ the meta data completely.
+    #[must_use]
+    pub(crate) fn read_unvalidated_from_buffered_peekable(
+        read: &mut PeekRead<impl Read>, pedantic: bool, registry: Option<&AttributeTypeRegistry>
+    ) -> Result<Self> {
+        Self::read_unvalidated_parts(read, pedantic, registry, false).map(|(meta_data, _)| meta_data)
+    }
 
-        let headers = Header::read_all(read, &requirements, pedantic)?;
+    /// Does __not validate__ the meta data completely.
+    /// If desired, parts with an unknown type are not contained in the headers, but returned separately.
+    fn read_unvalidated_parts(
+        read: &mut PeekRead<impl Read>, pedantic: bool, registry: Option<&AttributeTypeRegistry>, skip_unknown_parts: bool
+    ) -> Result<(Self, Vec<UnknownPart>)> {
+        let requirements = magic_number::skip_identification_bytes(read)?;
+        let (headers, unknown_parts) = Header::read_all_parts(read, &requirements, pedantic, registry, skip_unknown_parts)?;
 
         // TODO check if supporting requirements 2 always implies supporting requirements 1
-        Ok(MetaData { requirements, headers })
+        Ok((MetaData { requirements, headers }, unknown_parts))
     }
 
     /// Validates the meta data.
     #[must_use]
     pub(crate) fn read_validated_from_buffered_peekable(
-        read: &mut PeekRead<impl Read>, pedantic: bool
+        read: &mut PeekRead<impl Read>, pedantic: bool, registry: Option<&AttributeTypeRegistry>
     ) -> Result<Self> {
-        let meta_data = Self::read_unvalidated_from_buffered_peekable(read, !pedantic)?;
+        Self::read_validated_parts(read, pedantic, registry, false).map(|(meta_data, _)| meta_data)
+    }
+
+    /// Validates the meta data of all parts that have a known type.
+    /// If desired, parts with an unknown type are not contained in the headers, but returned separately.
+    #[must_use]
+    pub(crate) fn read_validated_parts(
+        read: &mut PeekRead<impl Read>, pedantic: bool, registry: Option<&AttributeTypeRegistry>, skip_unknown_parts: bool
+    ) -> Result<(Self, Vec<UnknownPart>)> {
+        let (meta_data, unknown_parts) = Self::read_unvalidated_parts(read, pedantic, registry, skip_unknown_parts)?;
         MetaData::validate(meta_data.headers.as_slice(), pedantic)?;
-        Ok(meta_data)
+        meta_data.validate_deep_data_flag()?;
+        Ok((meta_data, unknown_parts))
+    }
+
+    /// Check that the deep data flag of the file matches the type of its parts.
+    /// Single part files may declare deep data only in the flags, without a type attribute.
+    fn validate_deep_data_flag(&self) -> UnitResult {
+        let has_deep_part = self.headers.iter().any(|header| header.deep);
+        let may_omit_type = !self.requirements.has_multiple_layers;
+
+        if has_deep_part && !self.requirements.has_deep_data {
+            return Err(Error::invalid("deep part in file without deep data flag"));
+        }
+
+        if self.requirements.has_deep_data && !has_deep_part && !may_omit_type {
+            return Err(Error::invalid("deep data flag in file without deep parts"));
+        }
+
+        Ok(())
     }
 
     /// Validates the meta data and writes it to the stream.
@@ -411,12 +500,47 @@ impl MetaData {
         // that still could be read correctly in theory
         let minimal_requirements = Self::validate(headers, pedantic)?;
 
+        // reading such files is fine, but we should not produce them
+        for header in headers {
+            header.compression.supports(&header.channels)?;
+        }
+
         magic_number::write(write)?;
         minimal_requirements.write(write)?;
         Header::write_all(headers, write, minimal_requirements.has_multiple_layers)?;
         Ok(minimal_requirements)
     }
 
+    /// The number of parts, also called layers, in this file.
+    /// Available without decoding any pixel chunks.
+    pub fn part_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Whether the part at the specified index contains deep data.
+    /// Returns `None` if there is no part with that index.
+    pub fn is_deep(&self, part_index: usize) -> Option<bool> {
+        self.headers.get(part_index).map(|header| self.header_is_deep(header))
+    }
+
+    /// Whether the part at the specified index is divided into tiles instead of scan lines.
+    /// Returns `None` if there is no part with that index.
+    pub fn is_tiled(&self, part_index: usize) -> Option<bool> {
+        self.headers.get(part_index).map(|header| header.blocks.has_tiles())
+    }
+
+    /// The kind of each part in this file, in the order the parts appear in the file.
+    pub fn part_kinds(&self) -> impl '_ + Iterator<Item=BlockType> {
+        self.headers.iter().map(move |header| {
+            BlockType::new(self.header_is_deep(header), header.blocks.has_tiles())
+        })
+    }
+
+    // single part files may declare deep data only in the version flags
+    fn header_is_deep(&self, header: &Header) -> bool {
+        header.deep || (!self.requirements.has_multiple_layers && self.requirements.has_deep_data)
+    }
+
     /// Read one offset table from the reader for each header.
     pub fn read_offset_tables(read: &mut PeekRead<impl Read>, headers: &Headers) -> Result<OffsetTables> {
         headers.iter()
@@ -424,6 +548,27 @@ impl MetaData {
             .collect()
     }
 
+    /// Read one offset table from the reader for each header,
+    /// skipping the offset tables of the unknown parts in between.
+    pub(crate) fn read_offset_tables_of_known_parts(
+        read: &mut PeekRead<impl Read>, headers: &Headers, unknown_parts: &[UnknownPart]
+    ) -> Result<OffsetTables> {
+        let mut headers = headers.iter();
+        let mut offset_tables = OffsetTables::new();
+
+        for part_index in 0 .. headers.len() + unknown_parts.len() {
+            match unknown_parts.iter().find(|part| part.index == part_index) {
+                Some(part) => crate::io::skip_bytes(read, part.chunk_count * u64::BYTE_SIZE)?,
+                None => {
+                    let header = headers.next().expect("part count mismatch");
+                    offset_tables.push(u64::read_vec(read, header.chunk_count, u16::MAX as usize, None, "offset table size")?);
+                }
+            }
+        }
+
+        Ok(offset_tables)
+    }
+
     /// Skip the offset tables by advancing the reader by the required byte count.
     // TODO use seek for large (probably all) tables!
     pub fn skip_offset_tables(read: &mut PeekRead<impl Read>, headers: &Headers) -> Result<usize> {
@@ -470,7 +615,7 @@ impl MetaData {
             return Err(Error::invalid("at least one layer is required"));
         }
 
-        let deep = false; // TODO deep data
+        let deep = headers.iter().any(|header| header.deep);
         let is_multilayer = headers.len() > 1;
         let first_header_has_tiles = headers.iter().next()
             .map_or(false, |header| header.blocks.has_tiles());
@@ -488,11 +633,8 @@ impl MetaData {
             has_deep_data: deep,
         };
 
+        // deep headers can be written and read, but decoding deep pixels is rejected when reading the blocks
         for header in headers {
-            if header.deep { // TODO deep data (and then remove this check)
-                return Err(Error::unsupported("deep data not supported yet"));
-            }
-
             header.validate(is_multilayer, &mut minimal_requirements.has_long_names, pedantic)?;
         }
 
@@ -507,14 +649,13 @@ impl MetaData {
             }
         }*/
 
-        if pedantic { // check for duplicate header names
+        // check for duplicate header names, as parts are identified by their name.
+        // two parts without any name also count as duplicates
+        if is_multilayer {
             let mut header_names = HashSet::with_capacity(headers.len());
             for header in headers {
                 if !header_names.insert(&header.own_attributes.layer_name) {
-                    return Err(Error::invalid(format!(
-                        "duplicate layer name: `{}`",
-                        header.own_attributes.layer_name.as_ref().expect("header validation bug")
-                    )));
+                    return Err(Error::invalid("duplicate part name"));
                 }
             }
         }
@@ -648,6 +789,7 @@ impl Requirements {
 mod test {
     use super::*;
     use crate::meta::header::{ImageAttributes, LayerAttributes};
+    use crate::meta::attribute::LazyAttribute;
 
     #[test]
     fn round_trip_requirements() {
@@ -721,6 +863,30 @@ mod test {
         assert_eq!(meta, meta2);
     }
 
+    #[test]
+    fn count_parts_and_kinds() {
+        let channels = smallvec![ ChannelDescription::named("Y", SampleType::F16) ];
+
+        let scan_lines = Header::new(Text::from("scan lines"), (16, 8), channels.clone())
+            .with_encoding(Compression::Uncompressed, BlockDescription::ScanLines, LineOrder::Increasing);
+
+        let tiles = Header::new(Text::from("tiles"), (16, 8), channels);
+
+        let mut data: Vec<u8> = Vec::new();
+        MetaData::write_validating_to_buffered(&mut data, &[scan_lines, tiles], true).unwrap();
+        let meta = MetaData::read_from_buffered(data.as_slice(), false).unwrap();
+
+        assert_eq!(meta.part_count(), 2);
+        assert_eq!(meta.is_tiled(0), Some(false));
+        assert_eq!(meta.is_tiled(1), Some(true));
+        assert_eq!(meta.is_deep(0), Some(false));
+        assert_eq!(meta.is_deep(1), Some(false));
+        assert_eq!(meta.is_tiled(2), None);
+        assert_eq!(meta.is_deep(2), None);
+
+        assert_eq!(meta.part_kinds().collect::<Vec<_>>(), vec![ BlockType::ScanLine, BlockType::Tile ]);
+    }
+
     #[test]
     fn infer_low_requirements() {
         let header_version_1_short_names = Header {
@@ -817,5 +983,606 @@ mod test {
         assert_eq!(low_requirements.has_deep_data, false);
         assert_eq!(low_requirements.has_multiple_layers, true);
     }
-}
 
+    #[test]
+    fn reject_duplicate_part_names() {
+        let header = Header::new(
+            Text::new_or_panic("layer"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        let mut renamed = header.clone();
+        renamed.own_attributes.layer_name = Some(Text::new_or_panic("other layer"));
+        assert!(MetaData::validate(&[header.clone(), renamed], false).is_ok());
+
+        for &pedantic in &[false, true] {
+            match MetaData::validate(&[header.clone(), header.clone()], pedantic) {
+                Err(Error::Invalid(message)) => assert_eq!(message, "duplicate part name"),
+                other => panic!("duplicate part names not rejected: {:?}", other.map(|_| ())),
+            }
+        }
+
+        let mut unnamed = header.clone();
+        unnamed.own_attributes.layer_name = None;
+        assert!(MetaData::validate(&[unnamed.clone(), unnamed], false).is_err());
+    }
+
+    #[test]
+    fn read_custom_string_attribute() {
+        let manifest = r#"{"bunny":"13851a76"}"#;
+
+        let mut header = Header::new(
+            Text::new_or_panic("layer"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        header.own_attributes.owner = Some(Text::new_or_panic("rabbit"));
+        header.own_attributes.other.insert(Text::new_or_panic("cryptomatte/1a2b3c4/manifest"), AttributeValue::Text(Text::new_or_panic(manifest)));
+        header.own_attributes.other.insert(Text::new_or_panic("cryptomatte/1a2b3c4/count"), AttributeValue::I32(3));
+
+        let mut bytes = Vec::new();
+        header.write(&mut bytes).unwrap();
+
+        let requirements = Requirements {
+            file_format_version: 2, is_single_layer_and_tiled: false,
+            has_long_names: true, has_deep_data: false, has_multiple_layers: false,
+        };
+
+        let header = Header::read(&mut PeekRead::new(bytes.as_slice()), &requirements, true).unwrap();
+        assert_eq!(header.string_attribute("cryptomatte/1a2b3c4/manifest"), Some(manifest));
+        assert_eq!(header.string_attribute("owner"), Some("rabbit"));
+        assert_eq!(header.string_attribute("cryptomatte/1a2b3c4/count"), None);
+        assert_eq!(header.string_attribute("comments"), None);
+    }
+
+    #[test]
+    fn look_up_attribute_by_name() {
+        let mut header = Header::new(
+            Text::new_or_panic("layer"), (30, 40),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        ).with_position(Vec2(-10, -20));
+
+        header.own_attributes.other.insert(Text::new_or_panic("rabbits"), AttributeValue::I32(3));
+
+        assert_eq!(
+            header.attribute(b"dataWindow"),
+            Some(AttributeValue::IntegerBounds(IntegerBounds::new(Vec2(-10, -20), Vec2(30, 40))))
+        );
+
+        assert_eq!(header.attribute(b"compression"), Some(AttributeValue::Compression(header.compression)));
+        assert_eq!(header.attribute(b"rabbits"), Some(AttributeValue::I32(3)));
+        assert_eq!(header.attribute(b"carrots"), None);
+    }
+
+    #[test]
+    fn look_up_scalar_attributes() {
+        let mut header = Header::new(
+            Text::new_or_panic("layer"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        header.own_attributes.longitude = Some(-122.5);
+        header.own_attributes.other.insert(Text::new_or_panic("altitude"), AttributeValue::F64(8848.86));
+
+        let mut bytes = Vec::new();
+        header.write(&mut bytes).unwrap();
+
+        let requirements = Requirements {
+            file_format_version: 2, is_single_layer_and_tiled: false,
+            has_long_names: false, has_deep_data: false, has_multiple_layers: false,
+        };
+
+        let header = Header::read(&mut PeekRead::new(bytes.as_slice()), &requirements, true).unwrap();
+
+        assert_eq!(header.double_attribute(b"altitude"), Some(8848.86));
+        assert_eq!(header.float_attribute(b"altitude"), None);
+
+        assert_eq!(header.float_attribute(b"longitude"), Some(-122.5));
+        assert_eq!(header.double_attribute(b"longitude"), Some(-122.5));
+
+        assert_eq!(header.float_attribute(b"latitude"), None);
+        assert_eq!(header.double_attribute(b"name"), None);
+    }
+
+    #[test]
+    fn reject_negative_chunk_count() {
+        let header = Header::new(
+            Text::new_or_panic("layer"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        let mut bytes = Vec::new();
+        header.write(&mut bytes).unwrap();
+
+        let chunk_count = LazyAttribute::read_header(&mut PeekRead::new(Tracking::new(bytes.as_slice())), 256).unwrap()
+            .into_iter().find(|attribute| attribute.name == *"chunkCount").unwrap();
+
+        let position = chunk_count.value_byte_position;
+        bytes[position .. position + i32::BYTE_SIZE].copy_from_slice(&(-5_i32).to_le_bytes());
+
+        let requirements = Requirements {
+            file_format_version: 2, is_single_layer_and_tiled: false,
+            has_long_names: false, has_deep_data: false, has_multiple_layers: false,
+        };
+
+        for &pedantic in &[false, true] {
+            match Header::read(&mut PeekRead::new(bytes.as_slice()), &requirements, pedantic) {
+                Err(Error::Invalid(message)) => assert_eq!(message, "negative chunk count"),
+                other => panic!("negative chunk count not rejected: {:?}", other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn duplicate_required_attributes() {
+        let header = Header::new(
+            Text::new_or_panic("layer"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        ).with_encoding(Compression::ZIP1, BlockDescription::ScanLines, LineOrder::Increasing);
+
+        let mut bytes = Vec::new();
+        header.write(&mut bytes).unwrap();
+
+        // append a second compression attribute before the terminating null byte
+        let terminator = bytes.pop().unwrap();
+        attribute::write(header::standard_names::COMPRESSION, &AttributeValue::Compression(Compression::RLE), &mut bytes).unwrap();
+        bytes.push(terminator);
+
+        let requirements = Requirements {
+            file_format_version: 2, is_single_layer_and_tiled: false,
+            has_long_names: false, has_deep_data: false, has_multiple_layers: false,
+        };
+
+        let lenient = Header::read(&mut PeekRead::new(bytes.as_slice()), &requirements, false).unwrap();
+        assert_eq!(lenient.compression, Compression::ZIP1, "the first attribute should be kept");
+        assert!(lenient.own_attributes.other.is_empty());
+
+        match Header::read(&mut PeekRead::new(bytes.as_slice()), &requirements, true) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "duplicate required attribute"),
+            other => panic!("duplicate attribute not rejected: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn huge_chunk_counts_error_instead_of_allocating() {
+        let header = Header::new(
+            Text::new_or_panic("layer"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        let mut bytes = Vec::new();
+        header.write(&mut bytes).unwrap();
+
+        let chunk_count = LazyAttribute::read_header(&mut PeekRead::new(Tracking::new(bytes.as_slice())), 256).unwrap()
+            .into_iter().find(|attribute| attribute.name == *"chunkCount").unwrap();
+
+        let position = chunk_count.value_byte_position;
+        bytes[position .. position + i32::BYTE_SIZE].copy_from_slice(&2_000_000_000_i32.to_le_bytes());
+
+        let requirements = Requirements {
+            file_format_version: 2, is_single_layer_and_tiled: false,
+            has_long_names: false, has_deep_data: false, has_multiple_layers: false,
+        };
+
+        // the chunk count attribute is never trusted, but computed from the data window
+        let lenient = Header::read(&mut PeekRead::new(bytes.as_slice()), &requirements, false).unwrap();
+        assert_eq!(lenient.chunk_count, header.chunk_count);
+
+        match Header::read(&mut PeekRead::new(bytes.as_slice()), &requirements, true) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "chunk count not matching data size"),
+            other => panic!("huge chunk count not rejected: {:?}", other.map(|_| ())),
+        }
+
+        // a huge data window with one block per line implies a huge offset table,
+        // which must not be allocated before the offsets are actually read
+        let tall = Header::new(
+            Text::new_or_panic("tall"), (1, 1_000_000_000),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        ).with_encoding(Compression::Uncompressed, BlockDescription::ScanLines, LineOrder::Increasing);
+
+        assert_eq!(tall.chunk_count, 1_000_000_000);
+
+        let mut file = Vec::new();
+        MetaData::write_validating_to_buffered(&mut file, &[tall], false).unwrap();
+        file.extend_from_slice(&[0; 64]);
+
+        let mut read = PeekRead::new(file.as_slice());
+        let meta_data = MetaData::read_unvalidated_from_buffered_peekable(&mut read, false, None).unwrap();
+
+        match MetaData::read_offset_tables(&mut read, &meta_data.headers) {
+            Err(Error::Invalid(message)) => assert_eq!(message, MISSING_BYTES),
+            other => panic!("truncated offset table not rejected: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn dwa_compression_level_attribute() {
+        let header = |compression| Header::new(
+            Text::new_or_panic("dwa"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        ).with_encoding(compression, BlockDescription::ScanLines, LineOrder::Increasing);
+
+        assert_eq!(header(Compression::ZIP16).dwa_compression_level(), None);
+        assert_eq!(header(Compression::DWAB(None)).dwa_compression_level(), Some(45.0));
+
+        let dwa = header(Compression::DWAA(Some(60.0)));
+        assert_eq!(dwa.dwa_compression_level(), Some(60.0));
+
+        let mut data = Vec::new();
+        dwa.write(&mut data).unwrap();
+
+        let attributes = LazyAttribute::read_header(&mut PeekRead::new(Tracking::new(data.as_slice())), data.len()).unwrap();
+        assert!(attributes.iter().any(|attribute| attribute.name.as_slice() == header::standard_names::DWA_COMPRESSION_LEVEL));
+
+        let requirements = Requirements {
+            file_format_version: 2, is_single_layer_and_tiled: false,
+            has_long_names: false, has_deep_data: false, has_multiple_layers: false
+        };
+
+        let read = Header::read(&mut PeekRead::new(data.as_slice()), &requirements, false).unwrap();
+        assert_eq!(read.dwa_compression_level(), Some(60.0));
+    }
+
+    #[test]
+    fn frame_rate_from_rational_attribute() {
+        let header = Header::new(
+            Text::new_or_panic("sequence"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        assert_eq!(header.frame_rate(), None);
+
+        let mut header = header;
+        header.own_attributes.frames_per_second = Some((24000, 1001));
+
+        let mut data = Vec::new();
+        header.write(&mut data).unwrap();
+
+        let requirements = Requirements {
+            file_format_version: 2, is_single_layer_and_tiled: false,
+            has_long_names: false, has_deep_data: false, has_multiple_layers: false
+        };
+
+        let read = Header::read(&mut PeekRead::new(data.as_slice()), &requirements, false).unwrap();
+        let frame_rate = read.frame_rate().unwrap();
+        assert!((frame_rate - 23.976).abs() < 1.0e-3, "unexpected frame rate {}", frame_rate);
+
+        header.own_attributes.frames_per_second = Some((24, 0));
+        assert_eq!(header.frame_rate(), None);
+    }
+
+    #[test]
+    fn display_size_respects_pixel_aspect() {
+        let mut header = Header::new(
+            Text::new_or_panic("anamorphic"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        assert_eq!(header.display_size(), Vec2(4.0, 4.0));
+
+        header.shared_attributes.display_window = IntegerBounds::new((-2, 0), (8, 3));
+        header.shared_attributes.pixel_aspect = 2.0;
+        assert_eq!(header.display_size(), Vec2(16.0, 3.0));
+    }
+
+    #[test]
+    fn owner_comments_and_capture_date() {
+        let mut header = Header::new(
+            Text::new_or_panic("annotated"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        assert_eq!(header.owner(), None);
+        assert_eq!(header.comments(), None);
+        assert_eq!(header.capture_date(), None);
+
+        let date = CaptureDate { year: 2007, month: 3, day: 14, hour: 9, minute: 26, second: 53 };
+        header.own_attributes = header.own_attributes.clone()
+            .with_owner("Jane Doe").with_comments("first light").with_capture_date(date);
+
+        assert_eq!(header.own_attributes.capture_date, Some(Text::new_or_panic("2007:03:14 09:26:53")));
+        assert_eq!(header.owner(), Some(&Text::new_or_panic("Jane Doe")));
+        assert_eq!(header.comments(), Some(&Text::new_or_panic("first light")));
+        assert_eq!(header.capture_date(), Some(date));
+
+        let mut bytes = Vec::new();
+        MetaData::write_validating_to_buffered(&mut bytes, std::slice::from_ref(&header), true).unwrap();
+        let read_back = MetaData::read_from_buffered(bytes.as_slice(), true).unwrap();
+        assert_eq!(read_back.headers[0].owner(), header.owner());
+        assert_eq!(read_back.headers[0].comments(), header.comments());
+        assert_eq!(read_back.headers[0].capture_date(), Some(date));
+
+        for invalid in &["2007:03:14", "2007-03-14 09:26:53", "2007:13:14 09:26:53", "2007:03:14 09:2a:53"] {
+            assert_eq!(CaptureDate::parse(&Text::new_or_panic(*invalid)), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_wrap_modes() {
+        let mut header = Header::new(
+            Text::new_or_panic("texture"), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        assert_eq!(header.wrap_modes(), None);
+
+        header.own_attributes.wrap_mode_name = Some(Text::new_or_panic("periodic,clamp"));
+        assert_eq!(header.wrap_modes(), Some((WrapMode::Periodic, WrapMode::Clamp)));
+
+        header.own_attributes = header.own_attributes.clone().with_wrap_modes(WrapMode::Black, WrapMode::Mirror);
+        assert_eq!(header.own_attributes.wrap_mode_name, Some(Text::new_or_panic("black,mirror")));
+
+        let mut bytes = Vec::new();
+        MetaData::write_validating_to_buffered(&mut bytes, std::slice::from_ref(&header), true).unwrap();
+        let read_back = MetaData::read_from_buffered(bytes.as_slice(), true).unwrap();
+        assert_eq!(read_back.headers[0].wrap_modes(), Some((WrapMode::Black, WrapMode::Mirror)));
+
+        let parse = |text: &str| WrapMode::parse_pair(&Text::new_or_panic(text));
+        assert_eq!(parse("clamp"), Some((WrapMode::Clamp, WrapMode::Clamp)));
+        assert_eq!(parse("mirror, black"), Some((WrapMode::Mirror, WrapMode::Black)));
+
+        for invalid in &["", "repeat,clamp", "clamp,", "clamp,clamp,clamp"] {
+            assert_eq!(parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn reject_empty_tile_size() {
+        let mut header = Header::new(
+            Text::new_or_panic("tiles"), (16, 16),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        // assign directly, as computing the chunk count for empty tiles would divide by zero
+        header.blocks = BlockDescription::Tiles(TileDescription {
+            tile_size: Vec2(0, 0),
+            level_mode: LevelMode::Singular,
+            rounding_mode: RoundingMode::Down,
+        });
+
+        for &pedantic in &[false, true] {
+            match MetaData::validate(std::slice::from_ref(&header), pedantic) {
+                Err(Error::Invalid(message)) => assert_eq!(message, "tile size"),
+                other => panic!("empty tile size not rejected: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn reject_sampling_not_dividing_data_window() {
+        let mut subsampled = ChannelDescription::named("RY", SampleType::F16);
+        subsampled.sampling = Vec2(2, 1);
+
+        let header = |width: usize| Header::new(
+            Text::new_or_panic("chroma"), (width, 4),
+            smallvec![ subsampled.clone(), ChannelDescription::named("Y", SampleType::F16) ]
+        ).with_encoding(Compression::Uncompressed, BlockDescription::ScanLines, LineOrder::Increasing);
+
+        // the subsampled channel is not supported yet, but the window is checked before that
+        match MetaData::validate(&[ header(4) ], true) {
+            Err(Error::NotSupported(_)) => {},
+            other => panic!("even width not accepted: {:?}", other),
+        }
+
+        for &pedantic in &[false, true] {
+            match MetaData::validate(&[ header(5) ], pedantic) {
+                Err(Error::Invalid(message)) => assert_eq!(message, "channel sampling factor not dividing data window size"),
+                other => panic!("odd width not rejected: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn reject_deep_flag_without_deep_parts() {
+        let header = |name: &str| Header::new(
+            Text::new_or_panic(name), (4, 4),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        let headers = [ header("left"), header("right") ];
+
+        let file = |has_deep_data: bool| {
+            let requirements = Requirements {
+                file_format_version: 2, is_single_layer_and_tiled: false,
+                has_long_names: false, has_deep_data, has_multiple_layers: true,
+            };
+
+            let mut bytes = Vec::new();
+            magic_number::write(&mut bytes).unwrap();
+            requirements.write(&mut bytes).unwrap();
+            Header::write_all(&headers, &mut bytes, true).unwrap();
+            bytes
+        };
+
+        let flat = file(false);
+        assert!(MetaData::read_validated_from_buffered_peekable(&mut PeekRead::new(flat.as_slice()), true, None).is_ok());
+
+        let deep = file(true);
+        for &pedantic in &[false, true] {
+            match MetaData::read_validated_from_buffered_peekable(&mut PeekRead::new(deep.as_slice()), pedantic, None) {
+                Err(Error::Invalid(message)) => assert_eq!(message, "deep data flag in file without deep parts"),
+                other => panic!("deep flag with scan line parts not rejected: {:?}", other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn recognize_aces_container() {
+        let mut header = Header::new(
+            Text::new_or_panic("aces"), (4, 4),
+            smallvec![
+                ChannelDescription::named("B", SampleType::F16),
+                ChannelDescription::named("G", SampleType::F16),
+                ChannelDescription::named("R", SampleType::F16),
+            ]
+        ).with_encoding(Compression::Uncompressed, BlockDescription::ScanLines, LineOrder::Increasing);
+
+        assert!(!header.is_aces_container());
+
+        header.shared_attributes.chromaticities = Some(Chromaticities::ACES_AP0);
+        header.own_attributes.adopted_neutral = Some(Vec2(0.32168, 0.33767));
+        header.own_attributes.other.insert(Text::new_or_panic("acesImageContainerFlag"), AttributeValue::I32(1));
+        assert!(header.is_aces_container());
+
+        let mut compressed = header.clone();
+        compressed.compression = Compression::ZIP1;
+        assert!(!compressed.is_aces_container());
+
+        let mut rec709 = header.clone();
+        rec709.shared_attributes.chromaticities = Some(Chromaticities {
+            red: Vec2(0.64, 0.33), green: Vec2(0.3, 0.6),
+            blue: Vec2(0.15, 0.06), white: Vec2(0.3127, 0.329),
+        });
+
+        assert!(!rec709.is_aces_container());
+    }
+
+    #[test]
+    fn buffer_index_relative_to_data_window() {
+        let header = Header::new(
+            Text::new_or_panic("layer"), (30, 40),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        ).with_position(Vec2(-10, -20));
+
+        assert_eq!(header.to_buffer_index(Vec2(-10, -20)), Some(0));
+        assert_eq!(header.to_buffer_index(Vec2(-9, -20)), Some(1));
+        assert_eq!(header.to_buffer_index(Vec2(-10, -19)), Some(30));
+        assert_eq!(header.to_buffer_index(Vec2(19, 19)), Some(30 * 40 - 1));
+
+        assert_eq!(header.to_buffer_index(Vec2(-11, -20)), None);
+        assert_eq!(header.to_buffer_index(Vec2(20, -20)), None);
+        assert_eq!(header.to_buffer_index(Vec2(-10, 20)), None);
+        assert_eq!(header.to_buffer_index(Vec2(i32::MIN, i32::MAX)), None);
+
+        assert_eq!(header.from_buffer_index(0), Some(Vec2(-10, -20)));
+        assert_eq!(header.from_buffer_index(31), Some(Vec2(-9, -19)));
+        assert_eq!(header.from_buffer_index(30 * 40), None);
+
+        for index in 0 .. 30 * 40 {
+            let position = header.from_buffer_index(index).unwrap();
+            assert_eq!(header.to_buffer_index(position), Some(index));
+        }
+    }
+
+    #[test]
+    fn write_deep_block_type_and_max_samples() {
+        let mut header = Header::new(
+            Text::new_or_panic("deep"), (8, 8),
+            smallvec![ ChannelDescription::named("Z", SampleType::F32) ]
+        ).with_encoding(Compression::Uncompressed, BlockDescription::ScanLines, LineOrder::Increasing);
+
+        header.deep = true;
+        header.deep_data_version = Some(1);
+
+        let attributes: Vec<_> = header.all_named_attributes().collect();
+        assert!(attributes.contains(&(header::standard_names::BLOCK_TYPE, AttributeValue::BlockType(BlockType::DeepScanLine))));
+        assert!(attributes.contains(&(header::standard_names::MAX_SAMPLES, AttributeValue::I32(-1))), "unknown max samples must be written as -1");
+
+        for max_samples in [ None, Some(4) ] {
+            header.max_samples_per_pixel = max_samples;
+
+            // pedantic validation requires the max samples to be known
+            let pedantic = max_samples.is_some();
+
+            let mut data: Vec<u8> = Vec::new();
+            let requirements = MetaData::write_validating_to_buffered(&mut data, std::slice::from_ref(&header), pedantic).unwrap();
+            assert!(requirements.has_deep_data);
+
+            let read = MetaData::read_from_buffered(data.as_slice(), pedantic).unwrap();
+            assert!(read.requirements.has_deep_data);
+            assert!(read.headers[0].deep);
+            assert_eq!(read.headers[0].block_type(), BlockType::DeepScanLine);
+            assert_eq!(read.headers[0].max_samples_per_pixel, max_samples);
+        }
+    }
+
+    #[test]
+    fn default_missing_pixel_aspect_unless_pedantic() {
+        let header = Header::new(
+            Text::new_or_panic("layer"), (8, 8),
+            smallvec![ ChannelDescription::named("Y", SampleType::F16) ]
+        );
+
+        let mut data: Vec<u8> = Vec::new();
+        for (name, value) in header.all_named_attributes() {
+            if name != header::standard_names::PIXEL_ASPECT {
+                attribute::write(name, &value, &mut data).unwrap();
+            }
+        }
+
+        sequence_end::write(&mut data).unwrap();
+
+        let requirements = Requirements {
+            file_format_version: 2,
+            is_single_layer_and_tiled: false,
+            has_long_names: false,
+            has_deep_data: false,
+            has_multiple_layers: false
+        };
+
+        let read = Header::read(&mut PeekRead::new(data.as_slice()), &requirements, false).unwrap();
+        assert_eq!(read.shared_attributes.pixel_aspect, 1.0);
+        assert_eq!(read.own_attributes.screen_window_width, 1.0);
+        assert_eq!(read.own_attributes.screen_window_center, Vec2(0.0, 0.0));
+
+        let pedantic = Header::read(&mut PeekRead::new(data.as_slice()), &requirements, true);
+        assert!(pedantic.is_err(), "pedantic reading should require the pixel aspect ratio");
+
+        // the pedantic flag must be passed on unchanged when reading the whole meta data
+        let mut file: Vec<u8> = Vec::new();
+        magic_number::write(&mut file).unwrap();
+        requirements.write(&mut file).unwrap();
+        file.extend_from_slice(&data);
+
+        for &validated in &[ false, true ] {
+            let read = |pedantic| {
+                if validated { MetaData::read_validated_from_buffered_peekable(&mut PeekRead::new(file.as_slice()), pedantic, None) }
+                else { MetaData::read_from_buffered(file.as_slice(), pedantic) }
+            };
+
+            let read_leniently = read(false).unwrap();
+            assert_eq!(read_leniently.headers[0].shared_attributes.pixel_aspect, 1.0);
+            assert!(read(true).is_err(), "pedantic reading should require the pixel aspect ratio");
+        }
+    }
+
+    #[test]
+    fn reject_magic_number_with_garbage_version() {
+        let mut bytes = Vec::new();
+        magic_number::write(&mut bytes).unwrap();
+        0xDEAD_BEEF_u32.write(&mut bytes).unwrap();
+        bytes.extend_from_slice(&[0; 64]);
+
+        match magic_number::skip_identification_bytes(&mut bytes.as_slice()) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "file identifier missing"),
+            other => panic!("garbage version not rejected: {:?}", other),
+        }
+
+        assert!(MetaData::read_from_buffered(bytes.as_slice(), false).is_err());
+
+        let mut valid = Vec::new();
+        magic_number::write(&mut valid).unwrap();
+        2_u32.write(&mut valid).unwrap();
+
+        let requirements = magic_number::skip_identification_bytes(&mut valid.as_slice()).unwrap();
+        assert_eq!(requirements.file_format_version, 2);
+    }
+
+    #[test]
+    fn reject_dwa_for_integer_channels_when_writing() {
+        let mut header = Header::new(
+            Text::new_or_panic("ids"), (4, 4),
+            smallvec![ ChannelDescription::named("id", SampleType::U32) ]
+        );
+
+        header.compression = Compression::DWAA(None);
+
+        match MetaData::write_validating_to_buffered(&mut Vec::new(), std::slice::from_ref(&header), false) {
+            Err(Error::Invalid(message)) => assert!(message.contains("float channel"), "{}", message),
+            other => panic!("dwa compression not rejected for integer channels: {:?}", other.map(|_| ())),
+        }
+
+        header.compression = Compression::ZIP16;
+        assert!(MetaData::write_validating_to_buffered(&mut Vec::new(), std::slice::from_ref(&header), false).is_ok());
+    }
+}
@@ -143,7 +143,7 @@ fn check_all_files_in_repo<T>(
 
                 Ok(Err(Error::Io(io))) => Result::Error(format!("IoError: {:?}", io)),
                 Ok(Err(Error::Invalid(message))) => Result::Error(format!("Invalid: {:?}", message)),
-                Ok(Err(Error::Aborted)) => panic!("a test produced `Error::Abort`"),
+                Ok(Err(Error::Cancelled)) => panic!("a test produced `Error::Cancelled`"),
 
                 Err(_) => Result::Error("Panic".to_owned()),
             };
@@ -288,6 +288,175 @@ fn roundtrip_unusual_7() -> UnitResult {
     Ok(())
 }
 
+#[test]
+fn read_cancelled_after_first_chunk() -> UnitResult {
+    let size = Vec2(8, 16);
+    let image = Image::from_encoded_channels(
+        size,
+        Encoding::UNCOMPRESSED,
+        SpecificChannels::rgb(|Vec2(x, y)| (x as f32, y as f32, 0.5_f32))
+    );
+
+    let mut tmp_bytes = Vec::new();
+    image.write().non_parallel().to_buffered(&mut Cursor::new(&mut tmp_bytes))?;
+
+    let mut progress_calls = 0;
+    let result = read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().non_parallel()
+        .on_progress_cancellable(|progress| {
+            progress_calls += 1;
+            if progress > 0.0 { std::ops::ControlFlow::Break(()) }
+            else { std::ops::ControlFlow::Continue(()) }
+        })
+        .from_buffered(Cursor::new(&tmp_bytes));
+
+    assert!(matches!(result, Err(Error::Cancelled)), "reading should have been cancelled");
+    assert_eq!(progress_calls, 2, "reading should stop right after the first chunk");
+    Ok(())
+}
+
+#[test]
+fn read_rgba_by_prefixed_channel_names() -> UnitResult {
+    let size = Vec2(3, 2);
+    let channel = |name: &str, value: f32| AnyChannel::new(name, FlatSamples::F32(vec![ value; size.area() ]));
+
+    let write_and_read_rgba = |channels: AnyChannels<FlatSamples>| -> Result<PixelVec<(f32, f32, f32, f32)>> {
+        let image = Image::from_channels(size, channels);
+
+        let mut bytes = Vec::new();
+        image.write().to_buffered(Cursor::new(&mut bytes))?;
+
+        let image = read().no_deep_data().largest_resolution_level()
+            .rgba_channels(PixelVec::<(f32, f32, f32, f32)>::constructor, PixelVec::set_pixel)
+            .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))?;
+
+        Ok(image.layer_data.channel_data.pixels)
+    };
+
+    let stored_in_any_order = write_and_read_rgba(AnyChannels::sort(smallvec::smallvec![
+        channel("B", 3.0), channel("G", 2.0), channel("R", 1.0), channel("A", 0.5),
+    ]))?;
+
+    assert!(stored_in_any_order.pixels.iter().all(|&pixel| pixel == (1.0, 2.0, 3.0, 0.5)));
+
+    let prefixed_without_alpha = write_and_read_rgba(AnyChannels::sort(smallvec::smallvec![
+        channel("diffuse.b", 3.0), channel("diffuse.g", 2.0), channel("diffuse.r", 1.0),
+    ]))?;
+
+    assert!(prefixed_without_alpha.pixels.iter().all(|&pixel| pixel == (1.0, 2.0, 3.0, 1.0)));
+
+    let ambiguous = write_and_read_rgba(AnyChannels::sort(smallvec::smallvec![
+        channel("left.R", 1.0), channel("right.R", 1.0), channel("G", 2.0), channel("B", 3.0),
+    ]));
+
+    assert!(ambiguous.is_err(), "two red channels should not be matched");
+
+    // outside of the rgba readers, channels are only matched by their exact name
+    let mut bytes = Vec::new();
+    Image::from_channels(size, AnyChannels::sort(smallvec::smallvec![ channel("diffuse.r", 1.0) ]))
+        .write().to_buffered(Cursor::new(&mut bytes))?;
+
+    let read_red = |by_suffix: bool, name: &str| {
+        let channels = read().no_deep_data().largest_resolution_level().specific_channels();
+        let channels = if by_suffix { channels.required_by_suffix(name) } else { channels.required(name) };
+
+        channels.collect_pixels(PixelVec::<(f32,)>::constructor, PixelVec::set_pixel)
+            .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))
+    };
+
+    assert!(read_red(false, "R").is_err(), "exact names should be required");
+    assert!(read_red(false, "diffuse.r").is_ok());
+    assert!(read_red(true, "R").is_ok());
+    Ok(())
+}
+
+#[test]
+fn roundtrip_in_memory_vec() -> UnitResult {
+    let size = Vec2(9, 5);
+    let pixels = (0 .. size.area())
+        .map(|index| (index as f32, -1.5_f32, f16::from_f32(0.5), index as u32))
+        .collect();
+
+    let image = Image::from_channels(size, SpecificChannels::rgba(PixelVec::new(size, pixels)));
+    let bytes = image.write().to_vec()?;
+
+    let read_back = read().no_deep_data().largest_resolution_level()
+        .rgba_channels(PixelVec::<(f32, f32, f16, u32)>::constructor, PixelVec::set_pixel)
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))?;
+
+    assert_eq!(read_back.attributes, image.attributes);
+    assert_eq!(read_back.layer_data.size, size);
+    assert_eq!(read_back.layer_data.channel_data.pixels, image.layer_data.channel_data.pixels);
+    Ok(())
+}
+
+#[test]
+fn require_lossless_rejects_lossy_compression() {
+    let image_with_compression = |compression| Image::from_encoded_channels(
+        Vec2(8, 8),
+        Encoding { compression, .. Encoding::UNCOMPRESSED },
+        SpecificChannels::rgb(|Vec2(x, y)| (x as f32, y as f32, 0.5_f32))
+    );
+
+    let mut bytes = Vec::new();
+    let lossy = image_with_compression(Compression::B44)
+        .write().require_lossless().to_buffered(Cursor::new(&mut bytes));
+
+    assert!(matches!(lossy, Err(Error::NotSupported(_))), "lossy compression should be rejected");
+    assert!(bytes.is_empty(), "nothing should be written for rejected images");
+
+    image_with_compression(Compression::ZIP16)
+        .write().require_lossless().to_buffered(Cursor::new(&mut bytes))
+        .expect("lossless compression should be accepted");
+}
+
+#[test]
+fn unbuffered_write_is_buffered() -> UnitResult {
+    /// Counts the calls to `write`, which would each be a system call on an unbuffered file.
+    struct CountWrites { bytes: Cursor<Vec<u8>>, write_calls: usize }
+
+    impl std::io::Write for CountWrites {
+        fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+            self.write_calls += 1;
+            self.bytes.write(buffer)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> { self.bytes.flush() }
+    }
+
+    impl std::io::Seek for CountWrites {
+        fn seek(&mut self, position: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.bytes.seek(position)
+        }
+    }
+
+    // one scan line per chunk results in many small chunks
+    let image = Image::from_encoded_channels(
+        Vec2(16, 512),
+        Encoding::UNCOMPRESSED,
+        SpecificChannels::rgb(|Vec2(x, y)| (x as f32, y as f32, 0.5_f32))
+    );
+
+    let mut direct = CountWrites { bytes: Cursor::new(Vec::new()), write_calls: 0 };
+    image.write().non_parallel().to_buffered(&mut direct)?;
+
+    let mut buffered = CountWrites { bytes: Cursor::new(Vec::new()), write_calls: 0 };
+    image.write().non_parallel().to_unbuffered(&mut buffered)?;
+
+    assert_eq!(direct.bytes.get_ref(), buffered.bytes.get_ref(), "buffering should not change the file contents");
+    assert!(
+        buffered.write_calls * 10 < direct.write_calls,
+        "buffered writing used {} write calls, unbuffered writing used {}",
+        buffered.write_calls, direct.write_calls
+    );
+
+    let read_back = read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(buffered.bytes.get_ref()))?;
+
+    assert_eq!(read_back.layer_data.size, Vec2(16, 512));
+    Ok(())
+}
+
 #[test]
 #[cfg(target_endian = "big")] // TODO big endian pxr24
 fn pxr24_expect_error_on_big_endian(){
@@ -374,3 +543,1020 @@ fn test_mixed_roundtrip_with_compression(compression: Compression) {
     lossy_image.assert_equals_result(&lossy_image);
     original_image.assert_equals_result(&lossy_image);
 }
+
+#[test]
+fn roundtrip_empty_data_window() {
+    let image = Image::from_channels(
+        (0, 3), SpecificChannels::rgb(PixelVec::<(f32,f32,f32)>::new(Vec2(0, 3), Vec::new()))
+    );
+
+    let mut file_bytes = Vec::new();
+    image.write().non_parallel().skip_compatibility_checks()
+        .to_buffered(Cursor::new(&mut file_bytes)).unwrap();
+
+    let meta = MetaData::read_from_buffered(file_bytes.as_slice(), false).unwrap();
+    assert_eq!(meta.headers[0].layer_size, Vec2(0, 3));
+    assert_eq!(meta.headers[0].chunk_count, 0);
+
+    let read_image = read().no_deep_data().largest_resolution_level()
+        .rgb_channels(PixelVec::<(f32,f32,f32)>::constructor, PixelVec::set_pixel)
+        .first_valid_layer().all_attributes().non_parallel()
+        .from_buffered(Cursor::new(&file_bytes)).unwrap();
+
+    assert_eq!(read_image.layer_data.size, Vec2(0, 3));
+    assert!(read_image.layer_data.channel_data.pixels.pixels.is_empty());
+
+    // pedantic writing does not allow empty images
+    assert!(image.write().to_buffered(Cursor::new(Vec::new())).is_err());
+}
+
+#[test]
+fn roundtrip_display_window_larger_than_data_window() {
+    let size = Vec2(4, 3);
+    let pixels = PixelVec::new(size, vec![(0.5_f32, 0.25_f32, 1.0_f32); size.area()]);
+
+    let layer = Layer::new(
+        size, LayerAttributes::named("crop").with_position(Vec2(10, 20)),
+        Encoding::UNCOMPRESSED, SpecificChannels::rgb(pixels)
+    );
+
+    let display_window = IntegerBounds::new(Vec2(0, 0), Vec2(1920, 1080));
+    let image = Image::from_layer(layer).with_display_window(display_window);
+
+    let bytes = image.write().non_parallel().to_vec().unwrap();
+
+    let meta = MetaData::read_from_buffered(bytes.as_slice(), true).unwrap();
+    assert_eq!(meta.headers[0].shared_attributes.display_window, display_window);
+    assert_eq!(meta.headers[0].data_window(), IntegerBounds::new(Vec2(10, 20), size));
+
+    let read_image = read().no_deep_data().largest_resolution_level()
+        .rgb_channels(PixelVec::<(f32,f32,f32)>::constructor, PixelVec::set_pixel)
+        .first_valid_layer().all_attributes().non_parallel()
+        .from_buffered(Cursor::new(&bytes)).unwrap();
+
+    assert_eq!(read_image.attributes.display_window, display_window);
+    assert_eq!(read_image.layer_data.attributes.layer_position, Vec2(10, 20));
+    assert_eq!(read_image.layer_data.size, size);
+}
+
+#[test]
+fn read_only_preview_thumbnail() {
+    let path = "tests/images/valid/openexr/Tiles/Ocean.exr";
+    let preview = read_preview_from_file(path).unwrap().expect("preview missing");
+
+    let full_image = read_all_data_from_file(path).unwrap();
+    assert_eq!(Some(&preview), full_image.layer_data[0].attributes.preview.as_ref());
+    assert_eq!(preview.pixel_data.len(), preview.size.area() * 4);
+
+    let without_preview = "tests/images/valid/openexr/v2/Stereo/Balls.exr";
+    assert_eq!(read_preview_from_file(without_preview).unwrap(), None);
+}
+
+#[test]
+fn read_only_channel_lists() -> UnitResult {
+    let channels = read_channels_from_file("tests/images/valid/openexr/MultiView/Impact.exr")?;
+    assert_eq!(channels.len(), 1);
+
+    let names: Vec<String> = channels[0].list.iter().map(|channel| channel.name.to_string()).collect();
+    assert_eq!(names, vec![ "B", "G", "R", "right.B", "right.G", "right.R" ]);
+    assert!(channels[0].list.iter().all(|channel| channel.sample_type == SampleType::F16));
+
+    for path in &[ "tests/images/valid/openexr/v2/Stereo/composited.exr", "tests/images/valid/openexr/Beachball/multipart.0001.exr" ] {
+        let meta_data = exr::meta::MetaData::read_from_file(path, false)?;
+        let expected: Vec<_> = meta_data.headers.iter().map(|header| header.channels.clone()).collect();
+        assert_eq!(read_channels_from_file(path)?, expected, "channels of {}", path);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ignore_trailing_bytes_but_reject_truncated_chunks() {
+    let size = Vec2(7, 5);
+    let pixels = PixelVec::new(size, vec![(0.5_f32, 0.25_f32, 1.0_f32); size.area()]);
+    let image = Image::from_encoded_channels(size, Encoding::UNCOMPRESSED, SpecificChannels::rgb(pixels));
+    let bytes = image.write().non_parallel().to_vec().unwrap();
+
+    let read_rgb = |bytes: &[u8]| read().no_deep_data().largest_resolution_level()
+        .rgb_channels(PixelVec::<(f32,f32,f32)>::constructor, PixelVec::set_pixel)
+        .first_valid_layer().all_attributes().non_parallel()
+        .from_buffered(Cursor::new(bytes));
+
+    let mut padded = bytes.clone();
+    padded.extend_from_slice(&[0; 64]);
+    let padded_image = read_rgb(&padded).unwrap();
+    assert_eq!(padded_image.layer_data.channel_data.pixels, image.layer_data.channel_data.pixels);
+
+    let truncated = &bytes[.. bytes.len() - 10];
+    match read_rgb(truncated) {
+        Err(Error::Invalid(message)) => assert_eq!(message, "truncated chunk"),
+        other => panic!("truncated file not rejected: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn reconstruct_zeroed_offset_table() {
+    let size = Vec2(9, 40);
+    let pixels = PixelVec::new(size, (0..size.area()).map(|index| (index as f32, 0.5, 1.0)).collect());
+    let image = Image::from_encoded_channels(size, Encoding::UNCOMPRESSED, SpecificChannels::rgb(pixels));
+    let bytes = image.write().non_parallel().to_vec().unwrap();
+
+    // the first offset points directly behind the offset table
+    let meta_data = exr::meta::MetaData::read_from_buffered(bytes.as_slice(), false).unwrap();
+    let chunk_count = meta_data.headers[0].chunk_count;
+    let table_start = (0 .. bytes.len() - 8)
+        .find(|&position| {
+            let offset = u64::from_le_bytes(std::convert::TryInto::try_into(&bytes[position .. position + 8]).unwrap());
+            offset == (position + 8 * chunk_count) as u64
+        })
+        .expect("offset table not found");
+
+    let mut zeroed = bytes.clone();
+    for byte in &mut zeroed[table_start .. table_start + 8 * chunk_count] { *byte = 0; }
+
+    let read_rgb = || read().no_deep_data().largest_resolution_level()
+        .rgb_channels(PixelVec::<(f32,f32,f32)>::constructor, PixelVec::set_pixel)
+        .first_valid_layer().all_attributes().non_parallel();
+
+    assert!(read_rgb().pedantic().from_buffered(Cursor::new(&zeroed)).is_err());
+
+    let reconstructed = read_rgb().reconstruct_offset_tables()
+        .from_buffered(Cursor::new(&zeroed)).unwrap();
+
+    assert_eq!(reconstructed.layer_data.channel_data.pixels, image.layer_data.channel_data.pixels);
+
+    // in a truncated file, the blocks behind the first incomplete chunk are skipped
+    let complete_rows = 29;
+    let chunk_byte_size = 2 * 4 + size.width() * 3 * 4; // y coordinate, byte count, and one line of samples
+    let truncated = &zeroed[.. zeroed.len() - (size.height() - complete_rows) * chunk_byte_size + 50];
+
+    let reconstructed = read_rgb().reconstruct_offset_tables()
+        .from_buffered(Cursor::new(truncated)).unwrap();
+
+    let pixels = &reconstructed.layer_data.channel_data.pixels.pixels;
+    let original = &image.layer_data.channel_data.pixels.pixels;
+    let complete_pixels = complete_rows * size.width();
+
+    assert_eq!(pixels[.. complete_pixels], original[.. complete_pixels]);
+    assert!(pixels[complete_pixels ..].iter().all(|&pixel| pixel == (0.0, 0.0, 0.0)));
+}
+
+#[test]
+fn write_unsorted_channels_in_alphabetical_order() -> UnitResult {
+    let size = Vec2(3, 2);
+    let channel = |name: &str, value: f32| AnyChannel::new(name, FlatSamples::F32(vec![ value; size.area() ]));
+
+    let unsorted = AnyChannels { list: smallvec::smallvec![
+        channel("G", 2.0), channel("Z", 4.0), channel("B", 3.0), channel("R", 1.0),
+    ] };
+
+    let image = Image::from_channels(size, unsorted);
+    let mut bytes = Vec::new();
+    image.write().to_buffered(Cursor::new(&mut bytes))?;
+
+    let read_back = read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))?;
+
+    let channels: Vec<(Text, FlatSamples)> = read_back.layer_data.channel_data.list.into_iter()
+        .map(|channel| (channel.name, channel.sample_data))
+        .collect();
+
+    assert_eq!(channels, vec![
+        (Text::from("B"), FlatSamples::F32(vec![ 3.0; size.area() ])),
+        (Text::from("G"), FlatSamples::F32(vec![ 2.0; size.area() ])),
+        (Text::from("R"), FlatSamples::F32(vec![ 1.0; size.area() ])),
+        (Text::from("Z"), FlatSamples::F32(vec![ 4.0; size.area() ])),
+    ]);
+
+    Ok(())
+}
+
+#[test]
+fn roundtrip_uncompressed_scan_lines_exactly() -> UnitResult {
+    use exr::block::chunk::CompressedBlock;
+
+    let size = Vec2(5, 3);
+    let luma: Vec<f32> = (0 .. size.area()).map(|index| index as f32 * 0.1 - 0.3).collect();
+    let ids: Vec<u32> = (0 .. size.area()).map(|index| index as u32 * 1000 + 7).collect();
+
+    let channels = AnyChannels::sort(smallvec::smallvec![
+        AnyChannel::new("Y", FlatSamples::F32(luma.clone())),
+        AnyChannel::new("id", FlatSamples::U32(ids.clone())),
+    ]);
+
+    let image = Image::from_encoded_channels(size, Encoding::UNCOMPRESSED, channels);
+    let mut bytes = Vec::new();
+    image.write().non_parallel().to_buffered(Cursor::new(&mut bytes))?;
+
+    // each block contains a single line, with the samples of each channel stored one after another
+    let chunks = exr::block::read(Cursor::new(&bytes), true)?.all_chunks(true)?;
+    for (y, chunk) in chunks.enumerate() {
+        let block = match chunk?.compressed_block {
+            CompressedBlock::ScanLine(block) => block,
+            _ => panic!("expected a scan line block"),
+        };
+
+        let line = y * size.width() .. (y + 1) * size.width();
+        let expected: Vec<u8> = luma[line.clone()].iter().flat_map(|sample| sample.to_le_bytes().to_vec())
+            .chain(ids[line].iter().flat_map(|sample| sample.to_le_bytes().to_vec()))
+            .collect();
+
+        assert_eq!(block.y_coordinate, y as i32);
+        assert_eq!(block.compressed_pixels, expected);
+    }
+
+    let read_back = read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))?;
+
+    let list = &read_back.layer_data.channel_data.list;
+    assert_eq!(list[0].sample_data, FlatSamples::F32(luma));
+    assert_eq!(list[1].sample_data, FlatSamples::U32(ids));
+    Ok(())
+}
+
+#[test]
+fn tiles_are_anchored_at_negative_data_window_origin() -> UnitResult {
+    use exr::block::UncompressedBlock;
+    use exr::block::chunk::TileCoordinates;
+
+    let size = Vec2(100, 80);
+    let origin = Vec2(-64, -64);
+
+    // each sample contains its global position
+    let samples: Vec<f32> = (0 .. size.area())
+        .map(|index| {
+            let Vec2(x, y) = Vec2(index % size.width(), index / size.width()).to_i32() + origin;
+            (x * 1000 + y) as f32
+        })
+        .collect();
+
+    let layer = Layer::new(
+        size, LayerAttributes::named("crop").with_position(origin),
+        Encoding { compression: Compression::Uncompressed, blocks: Blocks::Tiles(Vec2(64, 64)), line_order: LineOrder::Increasing },
+        AnyChannels::sort(smallvec::smallvec![ AnyChannel::new("Y", FlatSamples::F32(samples.clone())) ])
+    );
+
+    let bytes = Image::from_layer(layer).write().non_parallel().to_vec()?;
+
+    let reader = exr::block::read(Cursor::new(&bytes), true)?;
+    let meta_data = reader.meta_data().clone();
+    let header = &meta_data.headers[0];
+
+    let first_tile = TileCoordinates { tile_index: Vec2(0, 0), level_index: Vec2(0, 0) };
+    assert_eq!(header.get_block_data_window_pixel_coordinates(first_tile)?, IntegerBounds::new(origin, (64, 64)));
+
+    let last_tile = TileCoordinates { tile_index: Vec2(1, 1), level_index: Vec2(0, 0) };
+    assert_eq!(header.get_block_data_window_pixel_coordinates(last_tile)?, IntegerBounds::new((0, 0), (36, 16)));
+
+    let mut found_first_tile = false;
+    for chunk in reader.all_chunks(true)? {
+        let block = UncompressedBlock::decompress_chunk(chunk?, &meta_data, true)?;
+        if block.index.pixel_position != Vec2(0, 0) { continue; }
+
+        found_first_tile = true;
+        assert_eq!(block.index.pixel_size, Vec2(64, 64));
+
+        let first_samples: Vec<f32> = block.data.chunks(4).take(2)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+
+        assert_eq!(first_samples, vec![ (-64 * 1000 - 64) as f32, (-63 * 1000 - 64) as f32 ]);
+    }
+
+    assert!(found_first_tile);
+
+    let read_back = read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))?;
+
+    assert_eq!(read_back.layer_data.attributes.layer_position, origin);
+    assert_eq!(read_back.layer_data.channel_data.list[0].sample_data, FlatSamples::F32(samples));
+    Ok(())
+}
+
+#[test]
+fn writing_rejects_degenerate_windows() {
+    let rgb = |size: Vec2<usize>| SpecificChannels::rgb(PixelVec::new(size, vec![(0.5_f32, 0.25_f32, 1.0_f32); size.area()]));
+    let write = |image: &Image<Layer<_>>| image.write().non_parallel().to_vec();
+
+    // inverted windows cannot be built through the public api, which uses `usize` sizes,
+    // but a window without any width is still rejected
+    let empty = Image::from_channels((0, 4), rgb(Vec2(0, 4)))
+        .with_display_window(IntegerBounds::from_dimensions((8, 8)));
+
+    match write(&empty) {
+        Err(Error::Invalid(message)) => assert_eq!(message, "empty data window"),
+        other => panic!("empty data window not rejected: {:?}", other.map(|_| ())),
+    }
+
+    let no_display = Image::from_channels((4, 4), rgb(Vec2(4, 4)))
+        .with_display_window(IntegerBounds::from_dimensions((0, 0)));
+
+    match write(&no_display) {
+        Err(Error::Invalid(message)) => assert_eq!(message, "empty display window"),
+        other => panic!("empty display window not rejected: {:?}", other.map(|_| ())),
+    }
+
+    // the data window may lie entirely outside of the display window
+    let outside = Image::from_layer(Layer::new(
+        (4, 4), LayerAttributes::named("outside").with_position(Vec2(-100, 50)),
+        Encoding::UNCOMPRESSED, rgb(Vec2(4, 4))
+    )).with_display_window(IntegerBounds::from_dimensions((8, 8)));
+
+    assert!(write(&outside).is_ok());
+}
+
+#[test]
+fn read_multi_view_names_and_channel_views() {
+    let meta = MetaData::read_from_file("tests/images/valid/openexr/MultiView/Adjuster.exr", false).unwrap();
+    let header = &meta.headers[0];
+
+    let views = vec![ "center".to_string(), "left".to_string(), "right".to_string() ];
+    assert_eq!(header.multi_view(), Some(views));
+
+    let view_of = |channel: &str| header.view_of_channel(&Text::from(channel));
+    assert_eq!(view_of("R").as_deref(), Some("center"));
+    assert_eq!(view_of("left.G").as_deref(), Some("left"));
+    assert_eq!(view_of("diffuse.right.B").as_deref(), Some("right"));
+    assert_eq!(view_of("diffuse.B"), None);
+
+    let stereo = MetaData::read_from_file("tests/images/valid/openexr/v2/Stereo/Trunks.exr", false).unwrap();
+    assert_eq!(stereo.headers[0].multi_view(), None);
+    assert_eq!(stereo.headers[0].view_of_channel(&Text::from("R")), None);
+}
+
+#[test]
+fn compressed_chunks_write_the_same_file() -> UnitResult {
+    use exr::block::writer::ChunksWriter;
+
+    let size = Vec2(40, 37);
+    let pixels = SpecificChannels::rgba(|position: Vec2<usize>| (
+        position.x() as f32 / 40.0, position.y() as f32, f16::from_f32(0.5), 1.0_f32,
+    ));
+
+    let encoding = Encoding {
+        compression: Compression::ZIP1,
+        blocks: Blocks::Tiles(Vec2(16, 16)),
+        line_order: LineOrder::Increasing,
+    };
+
+    let image = Image::from_encoded_channels(size, encoding, pixels);
+
+    for &parallel in &[false, true] {
+        let write = || if parallel { image.write() } else { image.write().non_parallel() };
+
+        let mut direct = Vec::new();
+        write().to_buffered(Cursor::new(&mut direct))?;
+
+        let (headers, chunks) = write().to_chunks()?;
+        let mut low_level = Vec::new();
+
+        exr::block::write(Cursor::new(&mut low_level), headers, true, |_meta, chunk_writer| {
+            for (index_in_header_increasing_y, chunk) in chunks {
+                chunk_writer.write_chunk(index_in_header_increasing_y, chunk)?;
+            }
+
+            Ok(())
+        })?;
+
+        assert_eq!(low_level, direct);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn read_rgba_u32_id_channel_exactly() -> UnitResult {
+    let size = Vec2(7, 5);
+    let ids: Vec<u32> = (0 .. size.area() as u32).map(|index| (1 << 24) + 1 + index * 3).collect();
+
+    let channels = AnyChannels::sort(smallvec::smallvec![
+        AnyChannel::new("R", FlatSamples::U32(ids.clone())),
+        AnyChannel::new("G", FlatSamples::F32(vec![0.25; size.area()])),
+        AnyChannel::new("B", FlatSamples::F32(vec![0.5; size.area()])),
+    ]);
+
+    let mut bytes = Vec::new();
+    Image::from_channels(size, channels).write().to_buffered(Cursor::new(&mut bytes))?;
+
+    let image = read().no_deep_data().largest_resolution_level()
+        .rgba_channels(
+            |resolution, _| vec![0_u32; resolution.area()],
+            move |ids: &mut Vec<u32>, position, (id, _, _, _): (u32, f32, f32, f32)| {
+                ids[position.y() * size.width() + position.x()] = id
+            }
+        )
+        .first_valid_layer().all_attributes()
+        .from_buffered(Cursor::new(&bytes))?;
+
+    // f32 would round most of these ids, as they do not fit into its 24-bit mantissa
+    assert!(ids.iter().any(|&id| id as f32 as u32 != id));
+    assert_eq!(image.layer_data.channel_data.pixels, ids);
+    Ok(())
+}
+
+#[test]
+fn mislabeled_line_order_reads_lines_at_their_own_coordinates() -> UnitResult {
+    use exr::block::chunk::CompressedBlock;
+
+    let size = Vec2(6, 20);
+    let pixels = PixelVec::new(size, (0 .. size.area()).map(|index| (index as f32, 0.5, 1.0)).collect());
+    let encoding = Encoding { line_order: LineOrder::Decreasing, .. Encoding::UNCOMPRESSED };
+    let image = Image::from_encoded_channels(size, encoding, SpecificChannels::rgb(pixels));
+    let mut bytes = image.write().non_parallel().to_vec()?;
+
+    // declare increasing line order, while the chunks are still stored with decreasing y
+    let attribute = b"lineOrder\0lineOrder\0\x01\0\0\0";
+    let position = (0 .. bytes.len() - attribute.len())
+        .find(|&position| &bytes[position .. position + attribute.len()] == attribute)
+        .expect("line order attribute not found");
+
+    assert_eq!(bytes[position + attribute.len()], 1);
+    bytes[position + attribute.len()] = 0;
+
+    let y_coordinates = exr::block::read(Cursor::new(&bytes), true)?.all_chunks(true)?
+        .map(|chunk| match chunk?.compressed_block {
+            CompressedBlock::ScanLine(block) => Ok(block.y_coordinate),
+            _ => panic!("expected a scan line block"),
+        })
+        .collect::<exr::error::Result<Vec<i32>>>()?;
+
+    assert!(y_coordinates.windows(2).all(|pair| pair[0] > pair[1]));
+
+    // each chunk contains its own y coordinate, so no line order override is required
+    let read_back = read().no_deep_data().largest_resolution_level()
+        .rgb_channels(PixelVec::<(f32,f32,f32)>::constructor, PixelVec::set_pixel)
+        .first_valid_layer().all_attributes().non_parallel()
+        .from_buffered(Cursor::new(&bytes))?;
+
+    assert_eq!(read_back.layer_data.encoding.line_order, LineOrder::Increasing);
+    assert_eq!(read_back.layer_data.channel_data.pixels, image.layer_data.channel_data.pixels);
+    Ok(())
+}
+
+#[test]
+fn roundtrip_custom_chromaticities() -> UnitResult {
+    let size = Vec2(3, 4);
+    let mut image = Image::from_channels(size, SpecificChannels::rgb(
+        PixelVec::new(size, vec![(0.25_f32, 0.5_f32, 1.0_f32); size.area()])
+    ));
+
+    let chromaticities = exr::meta::attribute::Chromaticities {
+        red: Vec2(0.68, 0.32),
+        green: Vec2(0.265, 0.69),
+        blue: Vec2(0.15, 0.06),
+        white: Vec2(0.314, 0.351),
+    };
+
+    chromaticities.validate()?;
+    image.attributes.chromaticities = Some(chromaticities);
+
+    let bytes = image.write().non_parallel().to_vec()?;
+    let read_back = read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))?;
+
+    assert_eq!(read_back.attributes.chromaticities, Some(chromaticities));
+    Ok(())
+}
+
+#[test]
+fn chunks_are_only_decompressed_on_demand() -> UnitResult {
+    use exr::block::{chunk::CompressedBlock, reader::ChunksReader, writer::ChunksWriter, UncompressedBlock};
+
+    let size = Vec2(8, 64);
+    let pixels = PixelVec::new(size, (0 .. size.area()).map(|index| (index as f32, 0.5, 1.0)).collect());
+    let encoding = Encoding { compression: Compression::ZIP16, .. Encoding::UNCOMPRESSED };
+    let image = Image::from_encoded_channels(size, encoding, SpecificChannels::rgb(pixels));
+
+    // replace the compressed pixels of the second chunk with bytes that cannot be decompressed
+    let (headers, mut chunks) = image.write().non_parallel().to_chunks()?;
+    match &mut chunks[1].1.compressed_block {
+        CompressedBlock::ScanLine(block) => block.compressed_pixels = vec![0xff; 64],
+        _ => panic!("expected a scan line block"),
+    }
+
+    let mut bytes = Vec::new();
+    exr::block::write(Cursor::new(&mut bytes), headers, true, |_meta, chunk_writer| {
+        for (index_in_header_increasing_y, chunk) in chunks {
+            chunk_writer.write_chunk(index_in_header_increasing_y, chunk)?;
+        }
+
+        Ok(())
+    })?;
+
+    // reading the compressed chunks never looks at the compressed pixels
+    let reader = exr::block::read(Cursor::new(&bytes), true)?.all_chunks(true)?;
+    let meta_data = reader.meta_data().clone();
+    let chunks = reader.collect::<exr::error::Result<Vec<_>>>()?;
+    assert_eq!(chunks.len(), 4);
+
+    let decompressed: Vec<bool> = chunks.into_iter()
+        .map(|chunk| UncompressedBlock::decompress_chunk(chunk, &meta_data, true).is_ok())
+        .collect();
+
+    assert_eq!(decompressed, vec![ true, false, true, true ]);
+    Ok(())
+}
+
+#[test]
+fn separate_channels_of_a_hand_made_multi_line_block() -> UnitResult {
+    use exr::block::{writer::ChunksWriter, BlockIndex, UncompressedBlock};
+    use exr::meta::{header::Header, BlockDescription, Headers};
+
+    // a single zip block contains all four lines of this image
+    let size = Vec2(3, 4);
+    let header = Header::new(
+        Text::from("two channels"), size,
+        smallvec::smallvec![
+            ChannelDescription::named("B", SampleType::F32),
+            ChannelDescription::named("G", SampleType::U32),
+        ]
+    ).with_encoding(Compression::ZIP16, BlockDescription::ScanLines, LineOrder::Increasing);
+
+    // each line contains all samples of `B`, followed by all samples of `G`
+    let mut data = Vec::new();
+    for y in 0 .. size.height() {
+        for x in 0 .. size.width() { data.extend_from_slice(&(y as f32 + x as f32 * 0.25).to_le_bytes()); }
+        for x in 0 .. size.width() { data.extend_from_slice(&(1000 * y as u32 + x as u32).to_le_bytes()); }
+    }
+
+    let block = UncompressedBlock {
+        index: BlockIndex { layer: 0, pixel_position: Vec2(0, 0), pixel_size: size, level: Vec2(0, 0) },
+        data,
+    };
+
+    let headers: Headers = smallvec::smallvec![ header ];
+    let chunk = block.compress_to_chunk(&headers)?;
+
+    let mut bytes = Vec::new();
+    exr::block::write(Cursor::new(&mut bytes), headers, true, |_meta, chunk_writer| {
+        chunk_writer.write_chunk(0, chunk)
+    })?;
+
+    let image = read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))?;
+
+    let channels = &image.layer_data.channel_data.list;
+    let positions = || (0 .. size.height()).flat_map(|y| (0 .. size.width()).map(move |x| (x, y)));
+
+    assert_eq!(channels[0].sample_data, FlatSamples::F32(positions().map(|(x, y)| y as f32 + x as f32 * 0.25).collect()));
+    assert_eq!(channels[1].sample_data, FlatSamples::U32(positions().map(|(x, y)| 1000 * y as u32 + x as u32).collect()));
+    Ok(())
+}
+
+#[test]
+fn reject_tiled_file_without_tiles_attribute() -> UnitResult {
+    let size = Vec2(20, 20);
+    let pixels = PixelVec::new(size, vec![(0.5_f32, 0.25_f32, 1.0_f32); size.area()]);
+    let encoding = Encoding { blocks: Blocks::Tiles(Vec2(8, 8)), .. Encoding::UNCOMPRESSED };
+    let mut bytes = Image::from_encoded_channels(size, encoding, SpecificChannels::rgb(pixels))
+        .write().non_parallel().to_vec()?;
+
+    // rename the attribute, so that it is read as an unknown custom attribute
+    let attribute = b"tiles\0tiledesc\0";
+    let position = (0 .. bytes.len() - attribute.len())
+        .find(|&position| &bytes[position .. position + attribute.len()] == attribute)
+        .expect("tiles attribute not found");
+
+    bytes[position + 4] = b'z';
+
+    for &pedantic in &[false, true] {
+        match exr::meta::MetaData::read_from_buffered(bytes.as_slice(), pedantic) {
+            Err(Error::Invalid(message)) => assert_eq!(message, "missing or invalid tiles attribute"),
+            other => panic!("missing tiles attribute not rejected: {:?}", other.map(|_| ())),
+        }
+    }
+
+    let image = read().no_deep_data().all_resolution_levels().all_channels()
+        .all_layers().all_attributes().from_buffered(Cursor::new(&bytes));
+
+    assert!(image.is_err());
+    Ok(())
+}
+
+#[test]
+fn read_images_of_the_same_size_into_a_reused_buffer() -> UnitResult {
+    use std::cell::Cell;
+
+    let size = Vec2(9, 7);
+    let write_rgba = |offset: f32| {
+        let pixels = PixelVec::new(size, (0 .. size.area())
+            .map(|index| (index as f32 + offset, offset, 0.5, 1.0)).collect());
+
+        Image::from_channels(size, SpecificChannels::rgba(pixels)).write().to_vec()
+    };
+
+    let files = vec![ write_rgba(0.0)?, write_rgba(100.0)? ];
+    let scratch: Cell<Vec<(f32, f32, f32, f32)>> = Cell::new(Vec::new());
+    let mut allocation = None;
+
+    for (offset, bytes) in [0.0, 100.0].iter().zip(&files) {
+        let image = read().no_deep_data().largest_resolution_level()
+            .rgba_channels(|resolution, _| PixelVec::reusing(scratch.take(), resolution), PixelVec::set_pixel)
+            .first_valid_layer().all_attributes()
+            .from_buffered(Cursor::new(bytes))?;
+
+        let pixels = image.layer_data.channel_data.pixels.pixels;
+        let expected: Vec<_> = (0 .. size.area()).map(|index| (index as f32 + offset, *offset, 0.5, 1.0)).collect();
+        assert_eq!(pixels, expected);
+
+        // the second image is decoded into the vector of the first image
+        assert_eq!(*allocation.get_or_insert(pixels.as_ptr()), pixels.as_ptr());
+        scratch.set(pixels);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn rewriting_a_file_reproduces_the_attribute_order() -> UnitResult {
+    let size = Vec2(4, 3);
+    let mut layer_attributes = LayerAttributes::named("annotated");
+    layer_attributes.owner = Some(Text::from("owner"));
+
+    for index in 0 .. 12 {
+        let name = Text::new_or_panic(format!("custom{}", (b'a' + index) as char));
+        layer_attributes.other.insert(name, AttributeValue::I32(index as i32));
+    }
+
+    let image = Image::from_layer(Layer::new(
+        size, layer_attributes, Encoding::FAST_LOSSLESS,
+        SpecificChannels::rgb(PixelVec::new(size, vec![(0.5_f32, 0.25_f32, 1.0_f32); size.area()]))
+    ));
+
+    let written = image.write().to_vec()?;
+
+    // split the header into its attributes, each consisting of name, type name, size, and value
+    let header_start = 8;
+    let mut attributes = Vec::new();
+    let mut position = header_start;
+
+    while written[position] != 0 {
+        let name_end = position + written[position ..].iter().position(|&byte| byte == 0).unwrap() + 1;
+        let type_end = name_end + written[name_end ..].iter().position(|&byte| byte == 0).unwrap() + 1;
+        let size = i32::from_le_bytes([written[type_end], written[type_end + 1], written[type_end + 2], written[type_end + 3]]);
+        let end = type_end + 4 + size as usize;
+
+        attributes.push(&written[position .. end]);
+        position = end;
+    }
+
+    // reversing the attributes moves the custom attributes in front of the required ones,
+    // and sorts them by descending name. the size of the header does not change,
+    // so the offset tables remain valid
+    let mut original = written[.. header_start].to_vec();
+    original.extend(attributes.iter().rev().flat_map(|attribute| attribute.iter().copied()));
+    original.extend_from_slice(&written[position ..]);
+    assert_eq!(original.len(), written.len());
+    assert_ne!(original, written);
+
+    let read_back = read().no_deep_data().largest_resolution_level().all_channels()
+        .all_layers().all_attributes().from_buffered(Cursor::new(&original))?;
+
+    assert_eq!(read_back.write().to_vec()?, original);
+
+    // images that were not read from a file still write custom attributes sorted by name
+    let positions: Vec<usize> = (0 .. 12_u8)
+        .map(|index| {
+            let name = format!("custom{}\0", (b'a' + index) as char).into_bytes();
+            (0 .. written.len() - name.len()).find(|&position| written[position ..].starts_with(&name)).unwrap()
+        })
+        .collect();
+
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    Ok(())
+}
+
+#[test]
+fn read_offset_table_after_borrowed_meta_data() -> UnitResult {
+    use std::io::Read;
+
+    let size = Vec2(5, 8);
+    let pixels = PixelVec::new(size, vec![(0.5_f32, 0.25_f32, 1.0_f32); size.area()]);
+    let bytes = Image::from_channels(size, SpecificChannels::rgb(pixels)).write().non_parallel().to_vec()?;
+
+    let mut reader = Cursor::new(&bytes);
+    let meta_data = exr::meta::MetaData::read_from(&mut reader, true)?;
+    let offset_table_start = reader.position();
+    let chunk_count = meta_data.headers[0].chunk_count as u64;
+
+    let mut first_offset = [0_u8; 8];
+    reader.read_exact(&mut first_offset)?;
+
+    // the first chunk follows directly behind the offset table
+    assert_eq!(u64::from_le_bytes(first_offset), offset_table_start + 8 * chunk_count);
+    Ok(())
+}
+
+#[test]
+fn write_f32_pixels_into_f16_channel() -> UnitResult {
+    let size = Vec2(3, 2);
+    let channels = SpecificChannels::build()
+        .with_channel_details::<f32>(ChannelDescription::named("Y", SampleType::F16))
+        .with_pixel_fn(|_position| (0.1_f32,));
+
+    let bytes = Image::from_channels(size, channels).write().to_vec()?;
+
+    let image = read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(&bytes))?;
+
+    // 0.1 lies between two half floats, and is rounded to the nearest one
+    let nearest = f16::from_bits(0x2E66);
+    assert_eq!(nearest, f16::from_f32(0.1));
+    assert!((nearest.to_f32() - 0.1).abs() < (f16::from_bits(0x2E65).to_f32() - 0.1).abs());
+    assert!((nearest.to_f32() - 0.1).abs() < (f16::from_bits(0x2E67).to_f32() - 0.1).abs());
+
+    let luma = &image.layer_data.channel_data.list[0];
+    assert_eq!(luma.sample_data, FlatSamples::F16(vec![ nearest; size.area() ]));
+    Ok(())
+}
+
+#[test]
+fn strip_preview_when_writing() -> UnitResult {
+    let size = Vec2(4, 3);
+    let preview = exr::meta::attribute::Preview { size: Vec2(2, 1), pixel_data: vec![ 1, 2, 3, 4, 5, 6, 7, 8 ] };
+
+    let mut image = Image::from_channels(size, SpecificChannels::rgb(|_position| (0.5_f32, 0.25_f32, 1.0_f32)));
+    image.layer_data.attributes.preview = Some(preview.clone());
+
+    let read_preview = |bytes: &[u8]| read().no_deep_data().largest_resolution_level().all_channels()
+        .first_valid_layer().all_attributes().from_buffered(Cursor::new(bytes))
+        .map(|image| image.layer_data.attributes.preview);
+
+    let with_preview = image.write().to_vec()?;
+    assert_eq!(read_preview(&with_preview)?, Some(preview));
+
+    let without_preview = image.write().strip_preview().to_vec()?;
+    assert_eq!(read_preview(&without_preview)?, None);
+    assert!(without_preview.len() < with_preview.len());
+    assert!(image.layer_data.attributes.preview.is_some(), "the image itself must not be modified");
+    Ok(())
+}
+
+#[test]
+fn read_all_rgba_layers_of_a_multi_layer_file() -> UnitResult {
+    let size = Vec2(6, 4);
+    let layer = |name: &str, color: (f32, f32, f32, f32)| Layer::new(
+        size, LayerAttributes::named(name), Encoding::FAST_LOSSLESS,
+        SpecificChannels::rgba(move |_position| color)
+    );
+
+    let image = Image::empty(ImageAttributes::new(IntegerBounds::from_dimensions(size)))
+        .with_layer(layer("diffuse", (0.5, 0.25, 1.0, 1.0)))
+        .with_layer(layer("specular", (0.125, 0.0, 2.0, 0.5)));
+
+    let path = std::env::temp_dir().join("exrs_read_all_rgba_layers_of_a_multi_layer_file.exr");
+    image.write().to_file(&path)?;
+
+    let result = read_all_rgba_layers_from_file(
+        &path, PixelVec::<(f32, f32, f32, f32)>::constructor, PixelVec::set_pixel
+    );
+
+    std::fs::remove_file(&path)?;
+    let image = result?;
+
+    let names: Vec<Option<&Text>> = image.layer_data.iter().map(|layer| layer.attributes.layer_name.as_ref()).collect();
+    assert_eq!(names, vec![ Some(&Text::from("diffuse")), Some(&Text::from("specular")) ]);
+
+    assert!(image.layer_data[0].channel_data.pixels.pixels.iter().all(|&pixel| pixel == (0.5, 0.25, 1.0, 1.0)));
+    assert!(image.layer_data[1].channel_data.pixels.pixels.iter().all(|&pixel| pixel == (0.125, 0.0, 2.0, 0.5)));
+    Ok(())
+}
+
+#[test]
+fn scan_line_blocks_end_with_a_partial_block() -> UnitResult {
+    use exr::block::{reader::ChunksReader, UncompressedBlock};
+
+    let size = Vec2(5, 40);
+    let pixels = PixelVec::new(size, (0 .. size.area()).map(|index| (index as f32, 0.5, 1.0)).collect());
+
+    for &(compression, block_heights) in &[ (Compression::ZIP16, &[16, 16, 8][..]), (Compression::PIZ, &[32, 8][..]) ] {
+        let encoding = Encoding { compression, .. Encoding::UNCOMPRESSED };
+        let image = Image::from_encoded_channels(size, encoding, SpecificChannels::rgb(pixels.clone()));
+        let bytes = image.write().non_parallel().to_vec()?;
+
+        let reader = exr::block::read(Cursor::new(&bytes), true)?.all_chunks(true)?;
+        let meta_data = reader.meta_data().clone();
+
+        let mut blocks = reader
+            .map(|chunk| UncompressedBlock::decompress_chunk(chunk?, &meta_data, true))
+            .collect::<exr::error::Result<Vec<_>>>()?;
+
+        blocks.sort_by_key(|block| block.index.pixel_position.y());
+
+        let heights: Vec<usize> = blocks.iter().map(|block| block.index.pixel_size.height()).collect();
+        let positions: Vec<usize> = blocks.iter().map(|block| block.index.pixel_position.y()).collect();
+
+        assert_eq!(heights, block_heights, "block heights of {}", compression);
+        assert_eq!(positions, block_heights.iter().scan(0, |y, height| { let start = *y; *y += height; Some(start) }).collect::<Vec<_>>());
+        assert!(blocks.iter().all(|block| block.data.len() == block.index.pixel_size.area() * 3 * 4));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn read_half_float_rgba_exactly() -> UnitResult {
+    let size = Vec2(4, 2);
+
+    // subnormal, negative zero, infinity, and a nan with payload
+    let bits: [u16; 8] = [ 0x0001, 0x8000, 0x7C00, 0x7E01, 0x3C00, 0x3555, 0xFBFF, 0x0400 ];
+    let sample = |position: Vec2<usize>, channel: usize| f16::from_bits(bits[(position.flat_index_for_size(size) + channel) % bits.len()]);
+
+    let image = Image::from_channels(size, SpecificChannels::rgba(|position|
+        (sample(position, 0), sample(position, 1), sample(position, 2), sample(position, 3))
+    ));
+
+    let path = std::env::temp_dir().join("exrs_read_half_float_rgba_exactly.exr");
+    image.write().to_file(&path)?;
+
+    let result = read_first_rgba_layer_as_f16(&path);
+    std::fs::remove_file(&path)?;
+    let image = result?;
+
+    let pixels = &image.layer_data.channel_data.pixels;
+    for y in 0 .. size.height() {
+        for x in 0 .. size.width() {
+            let position = Vec2(x, y);
+            let pixel = pixels.get_pixel(position);
+
+            for channel in 0 .. 4 {
+                assert_eq!(pixel[channel].to_bits(), sample(position, channel).to_bits(), "channel {} at {:?}", channel, position);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn vendor_attribute_bytes_survive_rewriting() -> UnitResult {
+    use exr::meta::layout::FileLayout;
+
+    let size = Vec2(3, 3);
+    let mut image = Image::from_channels(size, SpecificChannels::rgb(|_position| (0.5_f32, 0.25_f32, 1.0_f32)));
+
+    let vendor = AttributeValue::Custom { kind: Text::from("acmeLensData"), bytes: vec![ 0, 7, 255, 3, 0, 0, 42 ] };
+    image.layer_data.attributes.other.insert(Text::from("acmeLens"), vendor);
+
+    let attribute_bytes = |file: &[u8]| -> exr::error::Result<Vec<u8>> {
+        let (_, layout) = FileLayout::read(&mut Cursor::new(file), true)?;
+        let attribute = layout.attributes[0].iter()
+            .find(|attribute| attribute.name == Text::from("acmeLens"))
+            .expect("vendor attribute missing");
+
+        Ok(file[attribute.bytes.clone()].to_vec())
+    };
+
+    let original = image.write().to_vec()?;
+
+    let read_image = read().no_deep_data().all_resolution_levels().all_channels()
+        .all_layers().all_attributes().from_buffered(Cursor::new(&original))?;
+
+    let rewritten = read_image.write().to_vec()?;
+    assert_eq!(attribute_bytes(&rewritten)?, attribute_bytes(&original)?);
+    Ok(())
+}
+
+#[test]
+fn read_custom_attribute_type_with_registry() -> UnitResult {
+    use exr::meta::attribute::AttributeTypeRegistry;
+    use exr::meta::MetaData;
+
+    let affine: [f32; 6] = [ 1.0, 0.5, 3.0, 0.0, 2.0, -4.0 ];
+    let bytes: Vec<u8> = affine.iter().flat_map(|value| value.to_le_bytes()).collect();
+
+    let size = Vec2(3, 3);
+    let mut image = Image::from_channels(size, SpecificChannels::rgb(|_position| (0.5_f32, 0.25_f32, 1.0_f32)));
+    let transform = AttributeValue::Custom { kind: Text::from("float3x2"), bytes: bytes.clone() };
+    image.layer_data.attributes.other.insert(Text::from("warp"), transform.clone());
+
+    let file = image.write().to_vec()?;
+
+    // embed the affine 2d transformation into a homogeneous 3x3 matrix
+    let registry = || AttributeTypeRegistry::default().with_type("float3x2", |bytes: &[u8]| {
+        let mut matrix = [ 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0 ];
+        for (value, bytes) in matrix.iter_mut().zip(bytes.chunks_exact(4)) {
+            *value = f32::from_le_bytes([ bytes[0], bytes[1], bytes[2], bytes[3] ]);
+        }
+
+        Ok(AttributeValue::Matrix3x3(matrix))
+    });
+
+    let expected = AttributeValue::Matrix3x3([ 1.0, 0.5, 3.0, 0.0, 2.0, -4.0, 0.0, 0.0, 1.0 ]);
+
+    let read_all = || read().no_deep_data().largest_resolution_level().all_channels().first_valid_layer().all_attributes();
+
+    let without_registry = read_all().from_buffered(Cursor::new(&file))?;
+    assert_eq!(without_registry.layer_data.attributes.other.get(&Text::from("warp")), Some(&transform));
+
+    let with_registry = read_all().with_attribute_types(registry()).from_buffered(Cursor::new(&file))?;
+    assert_eq!(with_registry.layer_data.attributes.other.get(&Text::from("warp")), Some(&expected));
+
+    let meta_data = MetaData::read_from_buffered_with_registry(file.as_slice(), true, &registry())?;
+    assert_eq!(meta_data.headers[0].own_attributes.other.get(&Text::from("warp")), Some(&expected));
+    Ok(())
+}
+
+#[test]
+fn skip_unknown_part_types_in_multi_part_files() -> UnitResult {
+    use exr::block::reader::Reader;
+    use exr::meta::UnknownPart;
+
+    let layer = |name: &str, height: usize, color: (f32, f32, f32)| Layer::new(
+        Vec2(6, height), LayerAttributes::named(name), Encoding::FAST_LOSSLESS,
+        SpecificChannels::rgb(move |_position| color)
+    );
+
+    let image = Image::empty(ImageAttributes::new(IntegerBounds::from_dimensions(Vec2(6, 40))))
+        .with_layer(layer("diffuse", 4, (0.5, 0.25, 1.0)))
+        .with_layer(layer("volume", 40, (0.125, 0.0, 2.0)))
+        .with_layer(layer("specular", 20, (1.0, 2.0, 3.0)));
+
+    let mut file = image.write().to_vec()?;
+    let chunk_count = exr::meta::MetaData::read_from_buffered(file.as_slice(), false)?.headers[1].chunk_count;
+
+    // replace the type of the second part with a type of the same length that this library does not know
+    let type_attribute = b"type\0string\0\x0a\0\0\0tiledimage";
+    let type_positions: Vec<usize> = (0 .. file.len() - type_attribute.len())
+        .filter(|&position| file[position ..].starts_with(type_attribute))
+        .collect();
+
+    assert_eq!(type_positions.len(), 3);
+    let value_position = type_positions[1] + type_attribute.len() - 10;
+    file[value_position .. value_position + 10].copy_from_slice(b"volumetric");
+
+    let read_rgb = || read().no_deep_data().largest_resolution_level()
+        .rgb_channels(PixelVec::<(f32, f32, f32)>::constructor, PixelVec::set_pixel)
+        .all_layers().all_attributes();
+
+    assert!(matches!(read_rgb().from_buffered(Cursor::new(&file)), Err(Error::Invalid(_))));
+
+    for pedantic in [false, true] {
+        let read_rgb = || if pedantic { read_rgb().pedantic() } else { read_rgb() };
+        let image = read_rgb().skip_unknown_parts().from_buffered(Cursor::new(&file))?;
+
+        let names: Vec<Option<&Text>> = image.layer_data.iter().map(|layer| layer.attributes.layer_name.as_ref()).collect();
+        assert_eq!(names, vec![ Some(&Text::from("diffuse")), Some(&Text::from("specular")) ]);
+
+        assert!(image.layer_data[0].channel_data.pixels.pixels.iter().all(|&pixel| pixel == (0.5, 0.25, 1.0)));
+        assert!(image.layer_data[1].channel_data.pixels.pixels.iter().all(|&pixel| pixel == (1.0, 2.0, 3.0)));
+        assert_eq!(image.layer_data[1].size, Vec2(6, 20));
+
+        // the skipped parts are reported by the reader
+        let reader = Reader::read_from_buffered_skipping_unknown_parts(Cursor::new(&file), pedantic, None)?;
+        assert_eq!(reader.headers().len(), 2);
+        assert_eq!(reader.unknown_parts(), &[ UnknownPart {
+            index: 1,
+            name: Some(Text::from("volume")),
+            part_type: Text::from("volumetric"),
+            chunk_count,
+        } ]);
+
+        assert_eq!(read_rgb().from_chunks(reader)?, image);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn read_async_equals_read_buffered() -> UnitResult {
+    let size = Vec2(40, 30);
+
+    // the preview makes the headers larger than the first loaded block of bytes
+    let mut diffuse_attributes = LayerAttributes::named("diffuse");
+    diffuse_attributes.preview = Some(exr::meta::attribute::Preview {
+        size: Vec2(48, 48), pixel_data: (0 .. 48 * 48 * 4).map(|index| index as i8).collect()
+    });
+
+    let image = Image::empty(ImageAttributes::new(IntegerBounds::from_dimensions(size)))
+        .with_layer(Layer::new(
+            size, LayerAttributes::named("depth"), Encoding::UNCOMPRESSED,
+            AnyChannels::sort(smallvec::smallvec![ AnyChannel::new("Z", FlatSamples::F32(
+                (0 .. size.area()).map(|index| index as f32 * 0.5).collect()
+            )) ])
+        ))
+        .with_layer(Layer::new(
+            size, diffuse_attributes, Encoding::FAST_LOSSLESS,
+            SpecificChannels::rgba(|Vec2(x, y)| (x as f32, y as f32, 0.25, 1.0))
+        ));
+
+    let bytes = image.write().to_vec()?;
+
+    let all_layers = || read().no_deep_data().largest_resolution_level().all_channels().all_layers().all_attributes();
+    let buffered = all_layers().from_buffered(Cursor::new(&bytes))?;
+    let asynchronous = all_layers().read_async(Cursor::new(&bytes)).await?;
+    assert_eq!(asynchronous, buffered);
+    assert_eq!(asynchronous.layer_data.len(), 2);
+
+    // only loads the chunks of the diffuse layer
+    let rgba_layer = || read().no_deep_data().largest_resolution_level()
+        .rgba_channels(PixelVec::<(f32, f32, f32, f32)>::constructor, PixelVec::set_pixel)
+        .first_valid_layer().all_attributes();
+
+    let buffered = rgba_layer().from_buffered(Cursor::new(&bytes))?;
+    let asynchronous = rgba_layer().read_async(Cursor::new(&bytes)).await?;
+    assert_eq!(asynchronous, buffered);
+    assert_eq!(asynchronous.layer_data.attributes.layer_name, Some(Text::from("diffuse")));
+    Ok(())
+}